@@ -1,129 +1,610 @@
 use anyhow::Result;
-use crossterm::{
-    cursor::{Hide, Show},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use chrono::{DateTime, Local};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     text::{Span, Spans, Text},
-    widgets::{Paragraph, Widget},
+    widgets::{Block, Borders, Paragraph, Widget},
     Terminal,
 };
 use std::{
+    collections::HashSet,
     fs,
     io::{self, Stdout},
+    ops::Range,
     path::PathBuf,
+    process::{self, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::Sender,
+    },
+    thread,
 };
 use strum::{EnumIter, IntoEnumIterator};
+use unicode_width::UnicodeWidthChar;
 
-use crate::{markdown_renderer::parse_markdown_to_widgets, Command};
+use crate::{
+    args::STDIN_ARGUMENT,
+    config::{theme::Theme, Behaviour},
+    export::write_html,
+    file_watcher::WatcherMessage,
+    link_target::{link_target, LinkTarget},
+    markdown_renderer::{
+        layout::{DocumentLayout, HeadingPosition, LinkPosition, ScrollAnchor},
+        render_markdown,
+    },
+    search::{highlight_ranges, line_text, Search, SearchCommand},
+    terminal_guard::{restore_terminal, suspend_process, take_over_terminal, TerminalGuard},
+    Command,
+};
 
 const TABLINE_HEIGHT: u16 = 1;
+const STATUSLINE_HEIGHT: u16 = 1;
+// Source of the revisions telling layouts apart, 0 standing for not yet laid out.
+static NEXT_LAYOUT_REVISION: AtomicU64 = AtomicU64::new(1);
 pub struct ClosedApplication;
 impl ClosedApplication {
-    pub fn open(file_paths: Vec<PathBuf>) -> Result<OpenedApplication> {
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, Hide)?;
+    pub fn open(
+        file_paths: Vec<PathBuf>,
+        stdin_contents: Option<String>,
+        behaviour: Behaviour,
+        theme: Theme,
+        watcher_sender: Sender<WatcherMessage>,
+    ) -> Result<OpenedApplication> {
+        let terminal_guard = take_over_terminal()?;
         let mut application = OpenedApplication {
+            _terminal_guard: terminal_guard,
             terminal: Terminal::new_split(
-                CrosstermBackend::new(stdout),
-                vec![Constraint::Length(TABLINE_HEIGHT), Constraint::Min(1)],
+                CrosstermBackend::new(io::stdout()),
+                vec![
+                    Constraint::Length(TABLINE_HEIGHT),
+                    Constraint::Min(1),
+                    Constraint::Length(STATUSLINE_HEIGHT),
+                ],
                 Direction::Vertical,
             )?,
             focused_view_idx: 0,
-            markdown_views: file_paths
+            markdown_views: stdin_contents
+                .map(MarkdownView::from_stdin)
                 .into_iter()
-                .map(|file_path| MarkdownView {
-                    file_path,
-                    offset: TABLINE_HEIGHT,
-                })
+                .chain(file_paths.into_iter().map(MarkdownView::new))
                 .collect(),
+            search: None,
+            table_of_contents_visible: behaviour.table_of_contents,
+            behaviour,
+            theme,
+            watcher_sender,
+            status_message: None,
+            exported_html_paths: HashSet::new(),
         };
-        // TEMP: until I figure out what buffer size I want.
-        // just fo scroll testing purposes
-        application.terminal.resize_buffer_rel(0, 10);
         application.draw_all()?;
         Ok(application)
     }
 }
 
 pub struct OpenedApplication {
+    // Restores the terminal should the application be dropped without being closed.
+    _terminal_guard: TerminalGuard,
     terminal: Terminal<CrosstermBackend<Stdout>>,
     focused_view_idx: usize,
     markdown_views: Vec<MarkdownView>,
+    search: Option<Search>,
+    table_of_contents_visible: bool,
+    behaviour: Behaviour,
+    theme: Theme,
+    // Has files opened by following links watched for changes.
+    watcher_sender: Sender<WatcherMessage>,
+    // Outcome of the last link followed or export, shown until the next one.
+    status_message: Option<Spans<'static>>,
+    // Pages exported since starting, which exporting again may overwrite.
+    exported_html_paths: HashSet<PathBuf>,
 }
 
 impl OpenedApplication {
     pub fn close(self) -> io::Result<ClosedApplication> {
-        disable_raw_mode()?;
-        execute!(io::stdout(), LeaveAlternateScreen, Show)?;
+        restore_terminal()?;
         Ok(ClosedApplication {})
     }
 
+    /// Redraws everything once resumed, as the screen has been left meanwhile.
+    pub fn suspend(&mut self) -> Result<()> {
+        suspend_process()?;
+        self.draw_all()
+    }
+
     pub fn select_next_view(&mut self) -> Result<()> {
         if self.focused_view_idx != self.markdown_views.len() - 1 {
-            self.focused_view_idx += 1;
+            self.focus_view(self.focused_view_idx + 1);
         }
         self.draw_all()?;
         Ok(())
     }
 
     pub fn select_prev_view(&mut self) -> Result<()> {
-        self.focused_view_idx = self.focused_view_idx.saturating_sub(1);
+        self.focus_view(self.focused_view_idx.saturating_sub(1));
         self.draw_all()?;
         Ok(())
     }
 
+    /// Visiting a view clears its modified indicator.
+    fn focus_view(&mut self, view_index: usize) {
+        self.focused_view_idx = view_index;
+        self.markdown_views[view_index].modified_at = None;
+    }
+
     pub fn scroll_markdown_view(&mut self, steps: i16) -> Result<()> {
-        self.markdown_views[self.focused_view_idx].set_offset(steps);
-        self.terminal
-            .split_viewport_offset(|viewport_index| {
-                if viewport_index == ViewportIndex::Markdown as usize {
-                    (0, self.markdown_views[self.focused_view_idx].get_offset())
-                    // (0, 1)
+        let markdown_view_height = self.markdown_view_height();
+        self.markdown_views[self.focused_view_idx].scroll(steps, markdown_view_height);
+        self.draw_viewport(ViewportIndex::Markdown)?;
+        Ok(())
+    }
+
+    /// Scrolls by fractions of the view height, e.g. by half pages with a `divisor` of 2.
+    pub fn scroll_markdown_view_pages(&mut self, pages: i16, divisor: u16) -> Result<()> {
+        let page_height = (self.markdown_view_height() / divisor).max(1);
+        self.scroll_markdown_view(pages.saturating_mul(page_height as i16))
+    }
+
+    /// Scrolls to the given line, clamped so that the view stays within the document.
+    pub fn scroll_markdown_view_to(&mut self, scroll_offset: usize) -> Result<()> {
+        let markdown_view_height = self.markdown_view_height();
+        self.markdown_views[self.focused_view_idx].scroll_to(scroll_offset, markdown_view_height);
+        self.draw_viewport(ViewportIndex::Markdown)?;
+        Ok(())
+    }
+
+    pub fn toggle_table_of_contents(&mut self) -> Result<()> {
+        self.table_of_contents_visible = !self.table_of_contents_visible;
+        self.draw_viewport(ViewportIndex::Markdown)?;
+        Ok(())
+    }
+
+    /// Scrolls the next heading below the top of the view, or
+    /// the previous one above it if `backwards`, to the top.
+    pub fn jump_to_heading(&mut self, backwards: bool) -> Result<()> {
+        let markdown_view_height = self.markdown_view_height();
+        let focused_view = &mut self.markdown_views[self.focused_view_idx];
+        let scroll_offset = focused_view.scroll_offset;
+        let mut headings = focused_view.headings().iter();
+        let found_heading = if backwards {
+            headings.rfind(|heading| heading.line_index < scroll_offset)
+        } else {
+            headings.find(|heading| heading.line_index > scroll_offset)
+        };
+        if let Some(heading_line_index) = found_heading.map(|heading| heading.line_index) {
+            focused_view.scroll_to(heading_line_index, markdown_view_height);
+            self.draw_viewport(ViewportIndex::Markdown)?;
+        }
+        Ok(())
+    }
+
+    pub fn search(&mut self, search_command: SearchCommand) -> Result<()> {
+        let markdown_view_height = self.markdown_view_height();
+        let focused_view = &mut self.markdown_views[self.focused_view_idx];
+        match search_command {
+            SearchCommand::Open(direction) => {
+                self.search = Some(Search::new(direction, focused_view.scroll_offset));
+            }
+            SearchCommand::Cancel => {
+                // Leaving the prompt returns the view to where the search started.
+                if let Some(search) = self.search.take().filter(|search| search.prompting) {
+                    focused_view.scroll_to(search.origin_scroll_offset, markdown_view_height);
+                }
+            }
+            SearchCommand::Confirm => {
+                if let Some(search) = &mut self.search {
+                    search.prompting = false;
+                }
+                if self
+                    .search
+                    .as_ref()
+                    .is_some_and(|search| search.query.is_empty())
+                {
+                    self.search = None;
+                }
+            }
+            SearchCommand::NextMatch | SearchCommand::PrevMatch => {
+                let Some(search) = self.search.as_mut().filter(|search| !search.prompting) else {
+                    return Ok(());
+                };
+                search.refresh_matches(focused_view.lines(), focused_view.layout_revision);
+                search.select_next_match(matches!(search_command, SearchCommand::PrevMatch));
+                if let Some(current_match) = &search.current_match {
+                    focused_view.scroll_into_view(current_match.line_index, markdown_view_height);
+                }
+            }
+            SearchCommand::Input(_)
+            | SearchCommand::DeleteChar
+            | SearchCommand::ToggleIgnoreCase
+            | SearchCommand::ToggleRegex => {
+                let Some(search) = &mut self.search else {
+                    return Ok(());
+                };
+                match search_command {
+                    SearchCommand::Input(ch) => search.query.push(ch),
+                    SearchCommand::DeleteChar => {
+                        search.query.pop();
+                    }
+                    SearchCommand::ToggleIgnoreCase => search.ignore_case = !search.ignore_case,
+                    SearchCommand::ToggleRegex => search.regex = !search.regex,
+                    _ => (),
+                }
+                // Matches are searched for as the query is typed.
+                search.refresh_matches(focused_view.lines(), focused_view.layout_revision);
+                search.select_first_match();
+                match &search.current_match {
+                    Some(current_match) => focused_view
+                        .scroll_into_view(current_match.line_index, markdown_view_height),
+                    None => {
+                        focused_view.scroll_to(search.origin_scroll_offset, markdown_view_height)
+                    }
+                }
+            }
+        }
+        self.draw_viewport(ViewportIndex::Markdown)?;
+        self.draw_viewport(ViewportIndex::Statusline)?;
+        Ok(())
+    }
+
+    /// Selects the next link, or the previous one if `backwards`, starting
+    /// from the top of the view if no link is selected yet.
+    pub fn select_link(&mut self, backwards: bool) -> Result<()> {
+        let markdown_view_height = self.markdown_view_height();
+        let focused_view = &mut self.markdown_views[self.focused_view_idx];
+        let link_count = focused_view.links().len();
+        if link_count == 0 {
+            return Ok(());
+        }
+        let selected_link = match focused_view.selected_link {
+            Some(link_index) if backwards => (link_index + link_count - 1) % link_count,
+            Some(link_index) => (link_index + 1) % link_count,
+            None => {
+                let scroll_offset = focused_view.scroll_offset;
+                let mut links = focused_view.links().iter();
+                let found_link = if backwards {
+                    let view_end = scroll_offset + markdown_view_height as usize;
+                    links.rposition(|link| link.line_index() < view_end)
                 } else {
-                    (0, 0)
+                    links.position(|link| link.line_index() >= scroll_offset)
+                };
+                found_link.unwrap_or(0)
+            }
+        };
+        focused_view.selected_link = Some(selected_link);
+        let link_line_index = focused_view.links()[selected_link].line_index();
+        focused_view.scroll_into_view(link_line_index, markdown_view_height);
+        self.status_message = None;
+        self.draw_viewport(ViewportIndex::Markdown)?;
+        self.draw_viewport(ViewportIndex::Statusline)?;
+        Ok(())
+    }
+
+    /// Opens the target of the selected link. Markdown files are opened in a tab
+    /// of their own, or their existing one, and anything else with the opener.
+    pub fn follow_link(&mut self) -> Result<()> {
+        let focused_view = &self.markdown_views[self.focused_view_idx];
+        let Some(link) = focused_view
+            .selected_link
+            .and_then(|link_index| focused_view.links().get(link_index))
+        else {
+            return Ok(());
+        };
+        self.status_message = None;
+        match link_target(&focused_view.file_path, &link.url) {
+            LinkTarget::Anchor(anchor) => {
+                let origin = focused_view.location();
+                let focused_view = &mut self.markdown_views[self.focused_view_idx];
+                focused_view.back_history.push(origin);
+                focused_view.forward_history.clear();
+                focused_view.pending_anchor = Some(anchor);
+            }
+            LinkTarget::Document { file_path, anchor } => match fs::canonicalize(&file_path) {
+                Ok(file_path) => {
+                    let origin = focused_view.location();
+                    let view_index = self.open_view(file_path);
+                    let linked_view = &mut self.markdown_views[view_index];
+                    linked_view.back_history.push(origin);
+                    linked_view.forward_history.clear();
+                    linked_view.pending_anchor = anchor;
+                    self.focus_view(view_index);
+                }
+                Err(_) => {
+                    self.status_message =
+                        Some(self.error_status(format!(
+                            "Linked file not found: {}",
+                            file_path.display()
+                        )))
+                }
+            },
+            LinkTarget::External(target) => {
+                if let Err(error) = open_externally(&self.behaviour.opener, &target) {
+                    self.status_message = Some(self.error_status(format!(
+                        "Failed to open {target} with {}: {error}",
+                        self.behaviour.opener
+                    )));
                 }
-            })?
-            .unwrap_or(());
+            }
+        }
+        self.draw_all()?;
         Ok(())
     }
 
+    /// Returns to where the focused view was visited from, or
+    /// goes forward again to where it was returned from.
+    pub fn go_through_history(&mut self, backwards: bool) -> Result<()> {
+        let focused_view = &mut self.markdown_views[self.focused_view_idx];
+        let history = if backwards {
+            &mut focused_view.back_history
+        } else {
+            &mut focused_view.forward_history
+        };
+        let Some(location) = history.pop() else {
+            return Ok(());
+        };
+        let origin = focused_view.location();
+        // Tabs may have been closed since, along with their files.
+        let has_view = self
+            .markdown_views
+            .iter()
+            .any(|markdown_view| markdown_view.file_path == location.file_path);
+        if !has_view && !location.file_path.exists() {
+            self.status_message = Some(self.error_status(format!(
+                "File no longer exists: {}",
+                location.file_path.display()
+            )));
+            self.draw_viewport(ViewportIndex::Statusline)?;
+            return Ok(());
+        }
+        let view_index = self.open_view(location.file_path);
+        let visited_view = &mut self.markdown_views[view_index];
+        if backwards {
+            visited_view.forward_history.push(origin);
+        } else {
+            visited_view.back_history.push(origin);
+        }
+        visited_view.scroll_offset = location.scroll_offset;
+        visited_view.pending_anchor = None;
+        self.status_message = None;
+        self.focus_view(view_index);
+        self.draw_all()?;
+        Ok(())
+    }
+
+    /// Exports the focused view to a HTML page next to its file, or into the working
+    /// directory if read from stdin. Existing files are only overwritten if exported
+    /// earlier on, so as not to lose files that happen to share the name.
+    pub fn export_html(&mut self) -> Result<()> {
+        let focused_view = &self.markdown_views[self.focused_view_idx];
+        let (markdown_path, html_path) = match focused_view.stdin_contents {
+            Some(_) => (PathBuf::from("stdin"), PathBuf::from("stdin.html")),
+            None => (
+                focused_view.file_path.clone(),
+                focused_view.file_path.with_extension("html"),
+            ),
+        };
+        if html_path.exists() && !self.exported_html_paths.contains(&html_path) {
+            self.status_message = Some(self.error_status(format!(
+                "Not exporting to HTML, as {} already exists",
+                html_path.display()
+            )));
+            self.draw_viewport(ViewportIndex::Statusline)?;
+            return Ok(());
+        }
+        let export_result = focused_view
+            .read_markdown()
+            .map_err(anyhow::Error::from)
+            .and_then(|markdown_string| {
+                write_html(&markdown_string, &markdown_path, &html_path, &self.theme)
+            });
+        self.status_message = Some(match export_result {
+            Ok(()) => {
+                let status = Spans::from(Span::styled(
+                    format!("Exported to {}", html_path.display()),
+                    self.theme.status_success,
+                ));
+                self.exported_html_paths.insert(html_path);
+                status
+            }
+            Err(error) => self.error_status(format!("Failed to export to HTML: {error}")),
+        });
+        self.draw_viewport(ViewportIndex::Statusline)?;
+        Ok(())
+    }
+
+    /// Index of the view of the file, opening a new one if it has none.
+    fn open_view(&mut self, file_path: PathBuf) -> usize {
+        if let Some(view_index) = self
+            .markdown_views
+            .iter()
+            .position(|markdown_view| markdown_view.file_path == file_path)
+        {
+            return view_index;
+        }
+        self.send_watcher_message(WatcherMessage::Watch(file_path.clone()));
+        self.markdown_views.push(MarkdownView::new(file_path));
+        self.markdown_views.len() - 1
+    }
+
     pub fn update_view(&mut self, update: UpdateView) -> Result<Option<Command>> {
         match update {
+            UpdateView::Add(file_paths) => {
+                for file_path in file_paths {
+                    // Files may have been opened by following links in the meantime.
+                    if self.get_view_index(file_path.clone()).is_none() {
+                        self.markdown_views.push(MarkdownView::new(file_path));
+                    }
+                }
+                self.draw_viewport(ViewportIndex::Tabline)?
+            }
             UpdateView::Remove(file_paths) => {
                 for removed_path in file_paths {
-                    self.markdown_views
-                        .remove(self.get_view_index(removed_path));
-                    if self.markdown_views.is_empty() {
-                        return Ok(Some(Command::Close));
-                    } else if self.focused_view_idx == self.markdown_views.len() {
-                        self.focused_view_idx = self.focused_view_idx.saturating_sub(1);
+                    let Some(removed_view_index) = self.get_view_index(removed_path) else {
+                        continue;
+                    };
+                    if let Some(command) = self.remove_view(removed_view_index) {
+                        return Ok(Some(command));
+                    }
+                }
+                self.draw_all()?
+            }
+            UpdateView::Missing(file_paths) => {
+                for missing_path in file_paths {
+                    if let Some(view_index) = self.get_view_index(missing_path) {
+                        self.markdown_views[view_index].missing = true;
                     }
                 }
                 self.draw_all()?
             }
             UpdateView::Reload(file_paths) => {
                 for updated_path in file_paths {
-                    self.focused_view_idx = self.get_view_index(updated_path)
+                    let Some(view_index) = self.get_view_index(updated_path) else {
+                        continue;
+                    };
+                    self.markdown_views[view_index].missing = false;
+                    self.markdown_views[view_index].invalidate_layout();
+                    if self.behaviour.follow_changes {
+                        self.focus_view(view_index);
+                    } else if view_index != self.focused_view_idx {
+                        self.markdown_views[view_index].modified_at = Some(Local::now());
+                    }
                 }
-                self.draw_viewport(ViewportIndex::Markdown)?;
+                self.draw_all()?;
             }
             // Just re-render the view
             UpdateView::Resize => self.draw_all()?,
+            UpdateView::Warning(message) => {
+                self.status_message = Some(self.error_status(message));
+                self.draw_viewport(ViewportIndex::Statusline)?;
+            }
         }
         Ok(None)
     }
 
-    fn get_view_index(&self, file_path: PathBuf) -> usize {
+    /// Updates may still arrive for the files of views that have just been closed.
+    fn get_view_index(&self, file_path: PathBuf) -> Option<usize> {
         self.markdown_views
             .iter()
             .position(|buffer_view| file_path == *buffer_view.file_path)
-            .expect("File path with update exists in application tabs.")
+    }
+
+    /// Removes the view, asking to close the application once there are none left.
+    fn remove_view(&mut self, view_index: usize) -> Option<Command> {
+        self.markdown_views.remove(view_index);
+        if self.markdown_views.is_empty() {
+            return Some(Command::Close);
+        }
+        // Keep focus on the same view, or its neighbour if it was removed,
+        // which is then visited as if its tab had been selected.
+        if view_index < self.focused_view_idx {
+            self.focused_view_idx -= 1;
+        } else if view_index == self.focused_view_idx {
+            self.focus_view(view_index.min(self.markdown_views.len() - 1));
+        }
+        None
+    }
+
+    /// Focuses the clicked tab, jumps to the clicked table of
+    /// contents entry, or follows the clicked link.
+    pub fn click(&mut self, column: u16, row: u16) -> Result<()> {
+        if row < TABLINE_HEIGHT {
+            if let Some(view_index) = self.tab_index_at(column) {
+                self.focus_view(view_index);
+                self.draw_all()?;
+            }
+            return Ok(());
+        }
+        let (markdown_view_region, table_of_contents_region) = self.markdown_view_regions();
+        if let Some(table_of_contents_region) =
+            table_of_contents_region.filter(|region| region_contains(region, column, row))
+        {
+            let markdown_view_height = self.markdown_view_height();
+            let focused_view = &mut self.markdown_views[self.focused_view_idx];
+            let heading_index = focused_view
+                .first_table_of_contents_index(table_of_contents_region.height)
+                + (row - table_of_contents_region.y) as usize;
+            if let Some(heading_line_index) = focused_view
+                .headings()
+                .get(heading_index)
+                .map(|heading| heading.line_index)
+            {
+                focused_view.scroll_to(heading_line_index, markdown_view_height);
+                self.draw_viewport(ViewportIndex::Markdown)?;
+            }
+            return Ok(());
+        }
+        if !region_contains(&markdown_view_region, column, row) {
+            return Ok(());
+        }
+        let focused_view = &mut self.markdown_views[self.focused_view_idx];
+        let line_index = focused_view.scroll_offset + (row - markdown_view_region.y) as usize;
+        let Some(byte_offset) = focused_view
+            .lines()
+            .get(line_index)
+            .and_then(|line| byte_offset_at_column(line, column - markdown_view_region.x))
+        else {
+            return Ok(());
+        };
+        let clicked_link = focused_view.links().iter().position(|link| {
+            link.segments
+                .iter()
+                .any(|(segment_line_index, byte_range)| {
+                    *segment_line_index == line_index && byte_range.contains(&byte_offset)
+                })
+        });
+        if clicked_link.is_some() {
+            focused_view.selected_link = clicked_link;
+            self.follow_link()?;
+        }
+        Ok(())
+    }
+
+    /// Closes the clicked tab, asking to close the application along with the last one.
+    pub fn close_tab_at(&mut self, column: u16, row: u16) -> Result<Option<Command>> {
+        let Some(view_index) = self.tab_index_at(column).filter(|_| row < TABLINE_HEIGHT) else {
+            return Ok(None);
+        };
+        let file_path = self.markdown_views[view_index].file_path.clone();
+        self.send_watcher_message(WatcherMessage::Unwatch(file_path));
+        if let Some(command) = self.remove_view(view_index) {
+            return Ok(Some(command));
+        }
+        self.focus_view(self.focused_view_idx);
+        self.draw_all()?;
+        Ok(None)
+    }
+
+    fn tab_index_at(&self, column: u16) -> Option<usize> {
+        let first_visible_tab_index = self.first_visible_tab_index();
+        let mut tab_end = 0;
+        self.tabs()
+            .iter()
+            .skip(first_visible_tab_index)
+            .position(|tab| {
+                tab_end += tab.iter().map(Span::width).sum::<usize>();
+                (column as usize) < tab_end
+            })
+            .map(|visible_tab_index| first_visible_tab_index + visible_tab_index)
+    }
+
+    /// Tabs are scrolled just far enough for the focused tab to fit in the tabline.
+    fn first_visible_tab_index(&self) -> usize {
+        let tabline_width = self.terminal.get_buffer().get_width() as usize;
+        let tab_widths: Vec<usize> = self
+            .tabs()
+            .iter()
+            .map(|tab| tab.iter().map(Span::width).sum())
+            .collect();
+        let mut first_visible_tab_index = 0;
+        while first_visible_tab_index < self.focused_view_idx
+            && tab_widths[first_visible_tab_index..=self.focused_view_idx]
+                .iter()
+                .sum::<usize>()
+                > tabline_width
+        {
+            first_visible_tab_index += 1;
+        }
+        first_visible_tab_index
     }
 
     fn draw_all(&mut self) -> Result<()> {
@@ -142,94 +623,484 @@ impl OpenedApplication {
                     .render_widget_on_viewport(&tab_widget, viewport_index as usize);
             }
             ViewportIndex::Markdown => {
-                let focused_buffer = &self.markdown_views[self.focused_view_idx];
-                // TEMP: only one widget right now
-                // TODO: handle multiple widgets with offset
-                let found_markdown_widgets = Self::generate_markdown_widgets(focused_buffer)?;
-                if let Some(markdown_widgets) = found_markdown_widgets {
-                    let terminal_buffer = self.terminal.get_buffer();
-                    let markdowm_view_buffer_region = Rect {
-                        x: 0,
-                        y: TABLINE_HEIGHT,
-                        width: terminal_buffer.get_width(),
-                        height: terminal_buffer.get_height() - TABLINE_HEIGHT,
-                    };
-                    let markdown_view_widget_slots = Layout::default()
-                        .constraints(vec![Constraint::Max(10); markdown_widgets.len()])
-                        .split(&markdowm_view_buffer_region);
-                    for (index, widget) in markdown_widgets.iter().enumerate() {
-                        // let widget = *widget;
-                        self.terminal
-                            .render_widget(widget.as_ref(), &markdown_view_widget_slots[index]);
-                    }
+                let (markdown_view_region, table_of_contents_region) = self.markdown_view_regions();
+                let focused_view = &mut self.markdown_views[self.focused_view_idx];
+                // Documents are wrapped at the configured width if narrower than the terminal.
+                let layout_width = self
+                    .behaviour
+                    .wrap_width
+                    .map_or(markdown_view_region.width, |wrap_width| {
+                        wrap_width.min(markdown_view_region.width)
+                    });
+                focused_view.layout(layout_width, &self.theme)?;
+                if let Some(anchor) = focused_view.pending_anchor.take() {
+                    focused_view.scroll_to_anchor(&anchor, markdown_view_region.height);
+                }
+                // Resizes and reloads may leave the offset past the end of the document.
+                focused_view.scroll(0, markdown_view_region.height);
+                let mut visible_lines = focused_view.visible_lines(markdown_view_region.height);
+                if let Some(link) = focused_view.selected_link() {
+                    visible_lines = visible_lines
+                        .into_iter()
+                        .zip(focused_view.scroll_offset..)
+                        .map(|(line, line_index)| {
+                            highlight_link(line, line_index, link, self.theme.selected_link)
+                        })
+                        .collect();
+                }
+                if let Some(search) = &mut self.search {
+                    // Lines may have changed since last searched, e.g. on reload.
+                    search.refresh_matches(focused_view.lines(), focused_view.layout_revision);
+                    visible_lines = visible_lines
+                        .into_iter()
+                        .zip(focused_view.scroll_offset..)
+                        .map(|(line, line_index)| {
+                            search.highlight_line(line, line_index, &self.theme)
+                        })
+                        .collect();
+                }
+                if focused_view.missing {
+                    visible_lines = visible_lines
+                        .into_iter()
+                        .map(|line| greyed_out(line, self.theme.missing_document))
+                        .collect();
+                }
+                let visible_markdown_widget = Paragraph::new(Text::from(visible_lines));
+                self.terminal
+                    .render_widget(&visible_markdown_widget, &markdown_view_region);
+                if let Some(table_of_contents_region) = table_of_contents_region {
+                    let table_of_contents_widget = Self::table_of_contents_widget(
+                        focused_view,
+                        table_of_contents_region.height,
+                        &self.theme,
+                    );
+                    self.terminal
+                        .render_widget(&table_of_contents_widget, &table_of_contents_region);
                 }
             }
+            ViewportIndex::Statusline => {
+                let focused_view = &self.markdown_views[self.focused_view_idx];
+                let status = match (&self.search, &self.status_message) {
+                    (Some(search), _) => search.status(&self.theme),
+                    (None, Some(status_message)) => status_message.clone(),
+                    // The destination of the selected link is shown, as only its text is in view.
+                    (None, None) => focused_view
+                        .selected_link()
+                        .map(|link| Spans::from(link.url.clone()))
+                        .unwrap_or_default(),
+                };
+                self.terminal.render_widget_on_viewport(
+                    &Paragraph::new(Text::from(status)),
+                    viewport_index as usize,
+                );
+            }
         }
         self.terminal
             .flush_viewport_region(viewport_index as usize)?;
         Ok(())
     }
 
+    /// Region of the markdown viewport the document is drawn in,
+    /// along with that of the table of contents if visible.
+    fn markdown_view_regions(&self) -> (Rect, Option<Rect>) {
+        let markdown_view_region = Rect {
+            x: 0,
+            y: TABLINE_HEIGHT,
+            width: self.terminal.get_buffer().get_width(),
+            height: self.markdown_view_height(),
+        };
+        if !self.table_of_contents_visible {
+            return (markdown_view_region, None);
+        }
+        let table_of_contents_width = self
+            .behaviour
+            .table_of_contents_width
+            .min(markdown_view_region.width / 3);
+        let regions = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Length(table_of_contents_width),
+                Constraint::Min(1),
+            ])
+            .split(&markdown_view_region);
+        (regions[1], Some(regions[0]))
+    }
+
     fn tabline_widget(&mut self) -> impl Widget {
-        let mut tabs: Vec<Span> = Vec::with_capacity(self.markdown_views.len());
-        for MarkdownView { file_path, .. } in &self.markdown_views {
+        let tabs: Vec<Span> = self
+            .tabs()
+            .into_iter()
+            .skip(self.first_visible_tab_index())
+            .flatten()
+            .collect();
+        let tabline = Paragraph::new(Text::from(Spans::from(tabs)));
+        tabline
+    }
+
+    /// Spans of each tab in the tabline, in the order of their views.
+    fn tabs(&self) -> Vec<Vec<Span<'static>>> {
+        let mut tabs: Vec<Vec<Span>> = Vec::with_capacity(self.markdown_views.len());
+        for MarkdownView {
+            file_path,
+            stdin_contents,
+            modified_at,
+            missing,
+            ..
+        } in &self.markdown_views
+        {
             let absolute_file_path = file_path;
-            let tab_name = format!(
-                " {} ",
-                absolute_file_path
-                    .file_name()
-                    .expect("Path to be a valid file name")
-                    .to_string_lossy()
-            );
+            let tab_name = match stdin_contents {
+                Some(_) => " stdin ".to_string(),
+                None => format!(
+                    " {} ",
+                    absolute_file_path
+                        .file_name()
+                        .expect("Path to be a valid file name")
+                        .to_string_lossy()
+                ),
+            };
+            let mut tab = Vec::new();
             if *absolute_file_path == self.markdown_views[self.focused_view_idx].file_path {
-                tabs.push(Span::raw(tab_name));
+                tab.push(Span::styled(tab_name, self.theme.focused_tab));
             } else {
-                tabs.push(Span::styled(tab_name, Style::default().fg(Color::DarkGray)))
+                tab.push(Span::styled(tab_name, self.theme.tab))
             }
+            if let Some(modified_at) = modified_at {
+                tab.push(Span::styled(
+                    format!("● {} ", modified_at.format("%H:%M:%S")),
+                    self.theme.modified_tab,
+                ));
+            }
+            if *missing {
+                tab.push(Span::styled("✗ missing ", self.theme.missing_tab));
+            }
+            tabs.push(tab);
         }
-        let tabline = Paragraph::new(Text::from(Spans::from(tabs)));
-        tabline
+        tabs
     }
 
-    // TODO: resize buffer based on widgets and render widgets on that buffer
-    fn generate_markdown_widgets(
+    /// Heading hierarchy of the focused view, highlighting the section currently in view.
+    fn table_of_contents_widget(
         markdown_view: &MarkdownView,
-    ) -> Result<Option<Vec<Box<dyn Widget>>>> {
-        // Skip if file can't be read, happens in rare cases when OS file
-        // removals haven't had time to propagate through the file_watcher.
-        if markdown_view.file_path.exists() {
-            let file_string = fs::read_to_string(markdown_view.file_path.clone())?;
-            let markdown_widgets = parse_markdown_to_widgets(file_string);
-            Ok(Some(markdown_widgets))
-        } else {
-            Ok(None)
-        }
+        height: u16,
+        theme: &Theme,
+    ) -> impl Widget {
+        let current_heading_index = markdown_view.current_heading_index();
+        let first_shown_index = markdown_view.first_table_of_contents_index(height);
+        let entries: Vec<Spans> = markdown_view
+            .headings()
+            .iter()
+            .enumerate()
+            .skip(first_shown_index)
+            .map(|(heading_index, heading)| {
+                let indent = "  ".repeat(heading.level as usize - 1);
+                let entry_style = if Some(heading_index) == current_heading_index {
+                    theme.current_heading
+                } else {
+                    Style::default()
+                };
+                Spans::from(Span::styled(
+                    format!("{indent}{}", heading.title),
+                    entry_style,
+                ))
+            })
+            .collect();
+        Paragraph::new(Text::from(entries)).block(
+            Block::default()
+                .borders(Borders::RIGHT)
+                .border_style(theme.table_of_contents_border),
+        )
+    }
+
+    fn send_watcher_message(&self, watcher_message: WatcherMessage) {
+        // Only fails once the file watcher has stopped, which is reported on its own.
+        self.watcher_sender.send(watcher_message).ok();
+    }
+
+    fn error_status(&self, message: String) -> Spans<'static> {
+        Spans::from(Span::styled(message, self.theme.status_error))
+    }
+
+    fn markdown_view_height(&self) -> u16 {
+        self.terminal
+            .get_buffer()
+            .get_height()
+            .saturating_sub(TABLINE_HEIGHT + STATUSLINE_HEIGHT)
     }
 }
 
+fn region_contains(region: &Rect, column: u16, row: u16) -> bool {
+    (region.left()..region.right()).contains(&column)
+        && (region.top()..region.bottom()).contains(&row)
+}
+
+/// Byte offset within the line text of the char drawn at the column, if any.
+fn byte_offset_at_column(line: &Spans, column: u16) -> Option<usize> {
+    let mut line_width = 0;
+    line_text(line)
+        .char_indices()
+        .find_map(|(byte_offset, ch)| {
+            line_width += ch.width().unwrap_or(0);
+            (line_width > column as usize).then_some(byte_offset)
+        })
+}
+
+/// Highlights the parts of the selected link on the line, if any.
+fn highlight_link(
+    line: Spans<'static>,
+    line_index: usize,
+    link: &LinkPosition,
+    highlight_style: Style,
+) -> Spans<'static> {
+    let highlights: Vec<(Range<usize>, Style)> = link
+        .segments
+        .iter()
+        .filter(|(segment_line_index, _)| *segment_line_index == line_index)
+        .map(|(_, byte_range)| (byte_range.clone(), highlight_style))
+        .collect();
+    if highlights.is_empty() {
+        return line;
+    }
+    highlight_ranges(line, &highlights)
+}
+
+/// Runs the opener in the background, without it writing over the terminal.
+fn open_externally(opener: &str, target: &str) -> io::Result<()> {
+    let mut opener_words = opener.split_whitespace();
+    let program = opener_words.next().unwrap_or_default();
+    let mut opener_process = process::Command::new(program)
+        .args(opener_words)
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || opener_process.wait());
+    Ok(())
+}
+
+/// Last contents of missing files are shown greyed out.
+fn greyed_out(line: Spans<'static>, greyed_style: Style) -> Spans<'static> {
+    Spans::from(
+        line.0
+            .into_iter()
+            .map(|span| Span::styled(span.content, span.style.patch(greyed_style)))
+            .collect::<Vec<_>>(),
+    )
+}
+
 #[derive(EnumIter, Copy, Clone)]
 enum ViewportIndex {
     Tabline = 0,
     Markdown = 1,
+    Statusline = 2,
 }
 
 pub enum UpdateView {
+    // Files created under watched directories.
+    Add(Vec<PathBuf>),
     Remove(Vec<PathBuf>),
+    // Files removed but which may yet reappear, their last contents are kept on display.
+    Missing(Vec<PathBuf>),
     Reload(Vec<PathBuf>),
     Resize,
+    // Problems watching files that don't stop the rest from being watched.
+    Warning(String),
 }
 
 struct MarkdownView {
     file_path: PathBuf,
-    offset: u16,
+    // Markdown read from stdin, rendered in place of the file.
+    stdin_contents: Option<String>,
+    // Index of the first rendered line shown in the view.
+    scroll_offset: usize,
+    // Rendered file along with the width it was laid out for, `None` until first drawn.
+    rendered: Option<(u16, DocumentLayout)>,
+    // Unique to each layout of each view, so that searches know when to search anew.
+    layout_revision: u64,
+    // Set when the file changed since it was last rendered.
+    outdated: bool,
+    // When the file last changed in the background, cleared once visited.
+    modified_at: Option<DateTime<Local>>,
+    missing: bool,
+    // Index of the selected link, cleared whenever the document is laid out anew.
+    selected_link: Option<usize>,
+    // Heading anchor to scroll to once laid out, as linked to.
+    pending_anchor: Option<String>,
+    // Where the view was visited from by following links, and returned from.
+    back_history: Vec<Location>,
+    forward_history: Vec<Location>,
+}
+
+/// Place in a document, as kept in the history of views.
+struct Location {
+    file_path: PathBuf,
+    scroll_offset: usize,
 }
 
 impl MarkdownView {
-    pub fn get_offset(&self) -> u16 {
-        self.offset
+    fn new(file_path: PathBuf) -> Self {
+        MarkdownView {
+            file_path,
+            stdin_contents: None,
+            scroll_offset: 0,
+            rendered: None,
+            layout_revision: 0,
+            outdated: false,
+            modified_at: None,
+            missing: false,
+            selected_link: None,
+            pending_anchor: None,
+            back_history: Vec::new(),
+            forward_history: Vec::new(),
+        }
+    }
+
+    /// Links are followed relative to the working directory, as `-` has no parent directory.
+    fn from_stdin(stdin_contents: String) -> Self {
+        MarkdownView {
+            stdin_contents: Some(stdin_contents),
+            ..MarkdownView::new(PathBuf::from(STDIN_ARGUMENT))
+        }
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            file_path: self.file_path.clone(),
+            scroll_offset: self.scroll_offset,
+        }
+    }
+
+    /// Lays out the whole file for the given width, unless already done.
+    /// Keeps the view at the same place in the document if laid out anew.
+    pub fn layout(&mut self, width: u16, theme: &Theme) -> Result<()> {
+        if !self.outdated
+            && matches!(self.rendered, Some((rendered_width, _)) if rendered_width == width)
+        {
+            return Ok(());
+        }
+        // Skip if file can't be read, happens in rare cases when OS file
+        // removals haven't had time to propagate through the file_watcher.
+        if self.stdin_contents.is_none() && !self.file_path.exists() {
+            return Ok(());
+        }
+        let file_string = self.read_markdown()?;
+        let scroll_anchor = self.scroll_anchor();
+        self.rendered = Some((width, render_markdown(&file_string, width, theme)));
+        self.layout_revision = NEXT_LAYOUT_REVISION.fetch_add(1, Ordering::Relaxed);
+        self.outdated = false;
+        self.selected_link = None;
+        if let Some(scroll_anchor) = scroll_anchor {
+            self.restore_scroll_anchor(scroll_anchor);
+        }
+        Ok(())
+    }
+
+    fn read_markdown(&self) -> io::Result<String> {
+        match &self.stdin_contents {
+            Some(stdin_contents) => Ok(stdin_contents.clone()),
+            None => fs::read_to_string(&self.file_path),
+        }
+    }
+
+    pub fn invalidate_layout(&mut self) {
+        self.outdated = true;
+    }
+
+    fn scroll_anchor(&self) -> Option<ScrollAnchor> {
+        let (_, layout) = self.rendered.as_ref()?;
+        layout.scroll_anchor(self.scroll_offset)
+    }
+
+    fn restore_scroll_anchor(&mut self, scroll_anchor: ScrollAnchor) {
+        if let Some((_, layout)) = &self.rendered {
+            self.scroll_offset = layout.anchored_scroll_offset(&scroll_anchor);
+        }
+    }
+
+    /// Scrolls by the given amount of lines, stopping once
+    /// the top or the bottom of the document is in view.
+    pub fn scroll(&mut self, steps: i16, view_height: u16) {
+        self.scroll_to(
+            self.scroll_offset.saturating_add_signed(steps as isize),
+            view_height,
+        );
+    }
+
+    pub fn scroll_to(&mut self, scroll_offset: usize, view_height: u16) {
+        let max_scroll_offset = self.lines().len().saturating_sub(view_height as usize);
+        self.scroll_offset = scroll_offset.min(max_scroll_offset);
+    }
+
+    /// Scrolls the line into the middle of the view, unless already visible.
+    pub fn scroll_into_view(&mut self, line_index: usize, view_height: u16) {
+        let visible_range = self.scroll_offset..self.scroll_offset + view_height as usize;
+        if !visible_range.contains(&line_index) {
+            self.scroll_to(
+                line_index.saturating_sub(view_height as usize / 2),
+                view_height,
+            );
+        }
+    }
+
+    pub fn headings(&self) -> &[HeadingPosition] {
+        match &self.rendered {
+            Some((_, layout)) => &layout.headings,
+            None => &[],
+        }
+    }
+
+    /// Scrolls the heading with the anchor to the top, if there is such a heading.
+    pub fn scroll_to_anchor(&mut self, anchor: &str, view_height: u16) {
+        if let Some(heading_line_index) = self
+            .headings()
+            .iter()
+            .find(|heading| heading.anchor.eq_ignore_ascii_case(anchor))
+            .map(|heading| heading.line_index)
+        {
+            self.scroll_to(heading_line_index, view_height);
+        }
     }
 
-    pub fn set_offset(&mut self, steps: i16) {
-        self.offset = self.offset.saturating_add_signed(steps);
+    pub fn links(&self) -> &[LinkPosition] {
+        match &self.rendered {
+            Some((_, layout)) => &layout.links,
+            None => &[],
+        }
+    }
+
+    pub fn selected_link(&self) -> Option<&LinkPosition> {
+        self.selected_link
+            .and_then(|link_index| self.links().get(link_index))
+    }
+
+    /// Heading of the section at the top of the view.
+    pub fn current_heading_index(&self) -> Option<usize> {
+        let (_, layout) = self.rendered.as_ref()?;
+        layout.heading_index_at(self.scroll_offset)
+    }
+
+    /// Index of the first heading shown in a table of contents of the given height,
+    /// as long tables of contents follow the current section.
+    pub fn first_table_of_contents_index(&self, height: u16) -> usize {
+        self.current_heading_index()
+            .unwrap_or(0)
+            .saturating_sub(height as usize / 2)
+    }
+
+    pub fn lines(&self) -> &[Spans<'static>] {
+        match &self.rendered {
+            Some((_, layout)) => &layout.lines,
+            None => &[],
+        }
+    }
+
+    pub fn visible_lines(&self, view_height: u16) -> Vec<Spans<'static>> {
+        self.lines()
+            .iter()
+            .skip(self.scroll_offset)
+            .take(view_height as usize)
+            .cloned()
+            .collect()
     }
 }
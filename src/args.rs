@@ -1,21 +1,89 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
-use std::{fs, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, IsTerminal, Read},
+    path::PathBuf,
+};
+
+use crate::{config::Behaviour, file_discovery::find_markdown_files};
+
+// Stands in for a file argument to read markdown from stdin instead.
+pub const STDIN_ARGUMENT: &str = "-";
 
 #[derive(Parser)]
 #[command(author, version, about)]
 pub struct Args {
-    /// Markdown file to render view from
-    #[arg(required = true)]
+    /// Markdown files to render views from, or directories to open every markdown file under.
+    /// Markdown is read from stdin given `-`, or no files at all while piping into grow
     pub files: Vec<PathBuf>,
+    /// Directories given in place of files, set once parsed
+    #[arg(skip)]
+    pub directories: Vec<PathBuf>,
+    /// Markdown read from stdin, set once parsed
+    #[arg(skip)]
+    pub stdin_contents: Option<String>,
+    /// Config file to read in place of $XDG_CONFIG_HOME/grow/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Switch to the tab of a file whenever it changes
+    #[arg(long, overrides_with = "no_follow_changes")]
+    pub follow_changes: bool,
+    /// Stay on the focused tab when files change, even if the config file says to follow them
+    #[arg(long, overrides_with = "follow_changes")]
+    pub no_follow_changes: bool,
+    /// Seconds to keep showing a removed file before closing its tab, in case it reappears.
+    /// Defaults to 10
+    #[arg(long, value_name = "SECONDS")]
+    pub grace_period: Option<u64>,
+    /// Command to open links to anything but markdown files with, given the link as last argument.
+    /// Defaults to the system opener
+    #[arg(long, value_name = "COMMAND")]
+    pub opener: Option<String>,
+    /// Print the rendered files to stdout and exit, as plain text unless printing to a terminal
+    #[arg(long)]
+    pub print: bool,
+    /// Columns to render printed files at, defaults to the terminal width
+    /// or the configured wrap width, whichever is narrower
+    #[arg(long, value_name = "COLUMNS", requires = "print")]
+    pub width: Option<u16>,
+    /// Export the rendered file to a standalone HTML page and exit
+    #[arg(long, value_name = "HTML_FILE", conflicts_with = "print")]
+    pub export_html: Option<PathBuf>,
 }
 
 pub fn parse() -> Result<Args> {
     let args = Args::parse();
+    if args.files.is_empty() && io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "No files given, nor any markdown piped into stdin. See --help for usage."
+        ));
+    }
+    let reads_stdin = args.files.is_empty()
+        || args
+            .files
+            .iter()
+            .any(|file_path| file_path.as_os_str() == STDIN_ARGUMENT);
+    let mut stdin_contents = None;
+    if reads_stdin {
+        // Read in full up front, keyboard input then being read from the terminal itself.
+        let mut markdown_string = String::new();
+        io::stdin().read_to_string(&mut markdown_string)?;
+        stdin_contents = Some(markdown_string);
+    }
     let mut good_file_paths: Vec<PathBuf> = Vec::new();
+    let mut directories: Vec<PathBuf> = Vec::new();
     let mut bad_file_paths: Vec<PathBuf> = Vec::new();
     for file_path in args.files {
+        if file_path.as_os_str() == STDIN_ARGUMENT {
+            continue;
+        }
         match fs::canonicalize(file_path.clone()) {
+            Ok(absolute_path) if absolute_path.is_dir() => {
+                good_file_paths.extend(find_markdown_files(&absolute_path));
+                directories.push(absolute_path);
+            }
             Ok(absolute_file_path) => {
                 good_file_paths.push(absolute_file_path);
             }
@@ -24,11 +92,36 @@ pub fn parse() -> Result<Args> {
             }
         }
     }
+    // Files may be given both explicitly and through their directory.
+    let mut seen_file_paths = HashSet::new();
+    good_file_paths.retain(|file_path| seen_file_paths.insert(file_path.clone()));
     if !bad_file_paths.is_empty() {
         Err(anyhow!("Failed to locate files: {:?}", bad_file_paths))
+    } else if good_file_paths.is_empty() && stdin_contents.is_none() {
+        Err(anyhow!("No markdown files found in: {:?}", directories))
     } else {
         Ok(Args {
             files: good_file_paths,
+            directories,
+            stdin_contents,
+            ..args
         })
     }
 }
+
+impl Args {
+    /// Configured behaviour, overridden by the options given on the command line.
+    pub fn override_behaviour(&self, behaviour: Behaviour) -> Behaviour {
+        Behaviour {
+            // The last of the two flags given wins, the other being reset.
+            follow_changes: match (self.follow_changes, self.no_follow_changes) {
+                (true, _) => true,
+                (_, true) => false,
+                _ => behaviour.follow_changes,
+            },
+            grace_period: self.grace_period.unwrap_or(behaviour.grace_period),
+            opener: self.opener.clone().unwrap_or(behaviour.opener),
+            ..behaviour
+        }
+    }
+}
@@ -0,0 +1,103 @@
+pub mod keymap;
+#[cfg(test)]
+mod tests;
+pub mod theme;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use keymap::Keymap;
+use theme::Theme;
+
+// Relative to the user config directory.
+const CONFIG_FILE_PATH: &str = "grow/config.toml";
+
+#[cfg(target_os = "macos")]
+const DEFAULT_OPENER: &str = "open";
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_OPENER: &str = "xdg-open";
+
+/// Settings read from the config file, anything left out of it keeping its default.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub behaviour: Behaviour,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Behaviour {
+    /// Switch to the tab of a file whenever it changes.
+    pub follow_changes: bool,
+    /// Seconds to keep showing a removed file before closing its tab, in case it reappears.
+    pub grace_period: u64,
+    /// Command to open links to anything but markdown files with.
+    pub opener: String,
+    /// Columns to wrap documents at, at most, however wide the terminal.
+    pub wrap_width: Option<u16>,
+    /// Lines scrolled per step of the mouse wheel.
+    pub mouse_scroll_lines: i16,
+    /// Show the table of contents from the start.
+    pub table_of_contents: bool,
+    /// Columns taken up by the table of contents, at most a third of the terminal.
+    pub table_of_contents_width: u16,
+}
+
+impl Default for Behaviour {
+    fn default() -> Self {
+        Behaviour {
+            follow_changes: false,
+            grace_period: 10,
+            opener: DEFAULT_OPENER.to_string(),
+            wrap_width: None,
+            mouse_scroll_lines: 3,
+            table_of_contents: false,
+            table_of_contents_width: 30,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the given config file, or else the one in the user config directory.
+    /// Only the latter may be missing, in which case every setting keeps its default.
+    pub fn load(config_path: Option<&Path>) -> Result<Config> {
+        let (config_path, required) = match config_path {
+            Some(config_path) => (config_path.to_path_buf(), true),
+            None => match default_config_path() {
+                Some(config_path) => (config_path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let config_string = match fs::read_to_string(&config_path) {
+            Ok(config_string) => config_string,
+            Err(error) if error.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Config::default())
+            }
+            Err(error) => {
+                return Err(anyhow!(
+                    "Failed to read config file {}: {error}",
+                    config_path.display()
+                ))
+            }
+        };
+        // Parse errors point out the offending line of the file, along with what was expected.
+        toml::from_str(&config_string)
+            .map_err(|error| anyhow!("Invalid config file {}:\n{error}", config_path.display()))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/grow/config.toml`, with `~/.config` in place of an unset
+/// or relative `$XDG_CONFIG_HOME`, as per the XDG base directory specification.
+fn default_config_path() -> Option<PathBuf> {
+    let config_directory = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|config_directory| config_directory.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_directory.join(CONFIG_FILE_PATH))
+}
@@ -0,0 +1,250 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{
+    search::{SearchCommand, SearchDirection},
+    Command,
+};
+
+const DEFAULT_KEYMAP: &[(&str, KeyAction)] = &[
+    ("j", KeyAction::ScrollDown),
+    ("d", KeyAction::ScrollDown),
+    ("down", KeyAction::ScrollDown),
+    ("k", KeyAction::ScrollUp),
+    ("u", KeyAction::ScrollUp),
+    ("up", KeyAction::ScrollUp),
+    ("ctrl-d", KeyAction::HalfPageDown),
+    ("ctrl-u", KeyAction::HalfPageUp),
+    ("space", KeyAction::PageDown),
+    ("pagedown", KeyAction::PageDown),
+    ("pageup", KeyAction::PageUp),
+    ("g", KeyAction::Top),
+    ("home", KeyAction::Top),
+    ("G", KeyAction::Bottom),
+    ("end", KeyAction::Bottom),
+    ("/", KeyAction::SearchForward),
+    ("?", KeyAction::SearchBackward),
+    ("n", KeyAction::NextMatch),
+    ("N", KeyAction::PrevMatch),
+    ("t", KeyAction::ToggleTableOfContents),
+    ("e", KeyAction::ExportHtml),
+    ("]", KeyAction::NextHeading),
+    ("[", KeyAction::PrevHeading),
+    ("tab", KeyAction::NextLink),
+    ("shift-tab", KeyAction::PrevLink),
+    ("enter", KeyAction::FollowLink),
+    // Ctrl-i can't be told apart from Tab, so history is only
+    // gone forward through with Alt-Right.
+    ("backspace", KeyAction::HistoryBack),
+    ("ctrl-o", KeyAction::HistoryBack),
+    ("alt-left", KeyAction::HistoryBack),
+    ("alt-right", KeyAction::HistoryForward),
+    ("<", KeyAction::PrevView),
+    (">", KeyAction::NextView),
+    // Raw mode keeps the terminal from suspending on Ctrl-Z by itself.
+    ("ctrl-z", KeyAction::Suspend),
+    ("q", KeyAction::Close),
+    ("ctrl-c", KeyAction::Close),
+];
+
+/// Actions bound to keys, the keymap section of the config
+/// file only listing those that differ from the defaults.
+#[derive(Deserialize)]
+#[serde(from = "HashMap<KeyBinding, KeyAction>")]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, KeyAction>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_KEYMAP
+            .iter()
+            .map(|(key, action)| (key.parse().expect("Default keys to be valid"), *action))
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl From<HashMap<KeyBinding, KeyAction>> for Keymap {
+    fn from(bindings: HashMap<KeyBinding, KeyAction>) -> Self {
+        let mut keymap = Keymap::default();
+        keymap.bindings.extend(bindings);
+        keymap
+    }
+}
+
+impl Keymap {
+    pub fn command(&self, key_event: KeyEvent) -> Option<Command> {
+        self.bindings
+            .get(&KeyBinding::new(key_event.modifiers, key_event.code))
+            .and_then(|action| action.command())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    ScrollDown,
+    ScrollUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    SearchForward,
+    SearchBackward,
+    NextMatch,
+    PrevMatch,
+    ToggleTableOfContents,
+    ExportHtml,
+    NextHeading,
+    PrevHeading,
+    NextLink,
+    PrevLink,
+    FollowLink,
+    HistoryBack,
+    HistoryForward,
+    PrevView,
+    NextView,
+    Suspend,
+    Close,
+    /// Unbinds keys bound by default.
+    None,
+}
+
+impl KeyAction {
+    pub fn command(self) -> Option<Command> {
+        let command = match self {
+            KeyAction::ScrollDown => Command::Scroll(1),
+            KeyAction::ScrollUp => Command::Scroll(-1),
+            KeyAction::HalfPageDown => Command::ScrollHalfPage(1),
+            KeyAction::HalfPageUp => Command::ScrollHalfPage(-1),
+            KeyAction::PageDown => Command::ScrollPage(1),
+            KeyAction::PageUp => Command::ScrollPage(-1),
+            KeyAction::Top => Command::ScrollToTop,
+            KeyAction::Bottom => Command::ScrollToBottom,
+            KeyAction::SearchForward => {
+                Command::Search(SearchCommand::Open(SearchDirection::Forward))
+            }
+            KeyAction::SearchBackward => {
+                Command::Search(SearchCommand::Open(SearchDirection::Backward))
+            }
+            KeyAction::NextMatch => Command::Search(SearchCommand::NextMatch),
+            KeyAction::PrevMatch => Command::Search(SearchCommand::PrevMatch),
+            KeyAction::ToggleTableOfContents => Command::ToggleTableOfContents,
+            KeyAction::ExportHtml => Command::ExportHtml,
+            KeyAction::NextHeading => Command::NextHeading,
+            KeyAction::PrevHeading => Command::PrevHeading,
+            KeyAction::NextLink => Command::NextLink,
+            KeyAction::PrevLink => Command::PrevLink,
+            KeyAction::FollowLink => Command::FollowLink,
+            KeyAction::HistoryBack => Command::HistoryBack,
+            KeyAction::HistoryForward => Command::HistoryForward,
+            KeyAction::PrevView => Command::PrevView,
+            KeyAction::NextView => Command::NextView,
+            KeyAction::Suspend => Command::Suspend,
+            KeyAction::Close => Command::Close,
+            KeyAction::None => return None,
+        };
+        Some(command)
+    }
+}
+
+/// Key along with the modifiers held down with it, written like `ctrl-d`, `G` or `alt-left`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    modifiers: KeyModifiers,
+    code: KeyCode,
+}
+
+impl KeyBinding {
+    pub fn new(modifiers: KeyModifiers, code: KeyCode) -> Self {
+        // Some terminals report shifted characters and BackTab with the shift
+        // modifier and some without, so they're told apart by the key alone.
+        match code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) => KeyBinding {
+                modifiers: modifiers - KeyModifiers::SHIFT,
+                code: KeyCode::Char(ch.to_ascii_uppercase()),
+            },
+            KeyCode::BackTab => KeyBinding {
+                modifiers: modifiers - KeyModifiers::SHIFT,
+                code,
+            },
+            _ => KeyBinding { modifiers, code },
+        }
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<Self, Self::Error> {
+        key.parse()
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key_name = key;
+        // Trailing dashes are the minus key itself, as in `ctrl--`.
+        while let Some((modifier_name, rest)) = key_name
+            .split_once('-')
+            .filter(|(_, rest)| !rest.is_empty())
+        {
+            modifiers |= match modifier_name.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier `{modifier_name}` in key `{key}`, \
+                         expected `ctrl`, `alt` or `shift`"
+                    ))
+                }
+            };
+            key_name = rest;
+        }
+        let mut key_chars = key_name.chars();
+        let code = match (key_chars.next(), key_chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match key_name.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "esc" => KeyCode::Esc,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "insert" => KeyCode::Insert,
+                "delete" => KeyCode::Delete,
+                function_key => match function_key
+                    .strip_prefix('f')
+                    .and_then(|number| number.parse().ok())
+                {
+                    Some(number @ 1..=12) => KeyCode::F(number),
+                    _ => {
+                        return Err(format!(
+                            "unknown key `{key_name}` in key `{key}`, expected a single \
+                             character or a key name such as `space`, `enter` or `f1`"
+                        ))
+                    }
+                },
+            },
+        };
+        Ok(KeyBinding::new(modifiers, code))
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{
+    config::{keymap::KeyBinding, Config},
+    Command,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::{Color, Modifier, Style};
+
+#[test]
+fn keymap_rebinds_keys_on_top_of_defaults() {
+    let config: Config = toml::from_str("[keymap]\nj = \"page_down\"\nq = \"none\"\n").unwrap();
+    let command = |code| {
+        config
+            .keymap
+            .command(KeyEvent::new(code, KeyModifiers::NONE))
+    };
+    assert!(matches!(
+        command(KeyCode::Char('j')),
+        Some(Command::ScrollPage(1))
+    ));
+    assert!(command(KeyCode::Char('q')).is_none());
+    assert!(matches!(
+        command(KeyCode::Char('k')),
+        Some(Command::Scroll(-1))
+    ));
+}
+
+#[test]
+fn keys_are_parsed_with_their_modifiers() {
+    let key_binding = |key: &str| key.parse::<KeyBinding>().unwrap();
+    assert_eq!(
+        key_binding("ctrl-d"),
+        KeyBinding::new(KeyModifiers::CONTROL, KeyCode::Char('d'))
+    );
+    assert_eq!(
+        key_binding("shift-g"),
+        KeyBinding::new(KeyModifiers::SHIFT, KeyCode::Char('G'))
+    );
+    assert_eq!(key_binding("shift-g"), key_binding("G"));
+    assert_eq!(key_binding("shift-tab"), key_binding("backtab"));
+    assert_eq!(
+        key_binding("alt--"),
+        KeyBinding::new(KeyModifiers::ALT, KeyCode::Char('-'))
+    );
+    assert!("hyper-x".parse::<KeyBinding>().is_err());
+    assert!("ctrl-f13".parse::<KeyBinding>().is_err());
+}
+
+#[test]
+fn invalid_settings_are_reported_by_line() {
+    let error =
+        toml::from_str::<Config>("[behaviour]\nfollow_changes = true\n\n[keymap]\nj = \"scrol\"\n")
+            .err()
+            .unwrap()
+            .to_string();
+    assert!(error.contains("line 5"), "{error}");
+    assert!(error.contains("scrol"), "{error}");
+}
+
+#[test]
+fn theme_styles_replace_defaults() {
+    let config: Config = toml::from_str(
+        "[theme]\n\
+         h1 = { fg = \"#ff8000\", bg = 236, modifiers = [\"italic\"] }\n\
+         link = { fg = \"light_cyan\" }\n",
+    )
+    .unwrap();
+    assert_eq!(
+        config.theme.h1,
+        Style::default()
+            .fg(Color::Rgb(255, 128, 0))
+            .bg(Color::Indexed(236))
+            .add_modifier(Modifier::ITALIC)
+    );
+    assert_eq!(config.theme.link, Style::default().fg(Color::LightCyan));
+    assert!(toml::from_str::<Config>("[theme]\nh1 = { fg = \"#ff80\" }\n").is_err());
+}
+
+#[test]
+fn code_themes_are_checked_against_the_syntect_defaults() {
+    let config: Config = toml::from_str("[theme]\ncode_theme = \"InspiredGitHub\"\n").unwrap();
+    assert_eq!(config.theme.code_theme, "InspiredGitHub");
+    let error = toml::from_str::<Config>("[theme]\ncode_theme = \"Monokai\"\n")
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.contains("base16-ocean.dark"), "{error}");
+}
@@ -0,0 +1,293 @@
+use crate::markdown_renderer::code_block::code_theme_names;
+use pulldown_cmark::HeadingLevel;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer,
+};
+use std::fmt;
+
+/// Styles of the markdown elements and of the interface around them. Each style given in
+/// the theme section of the config file replaces the default style of its element whole,
+/// written like `h1 = { fg = "magenta", bg = "#1c1f26", modifiers = ["bold"] }`.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    #[serde(deserialize_with = "deserialize_style")]
+    pub h1: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub h2: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub h3: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub h4: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub h5: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub h6: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub link: Style,
+    /// Numbers referring links to their destination at the end of the document.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub link_reference: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub list_marker: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub footnote_marker: Style,
+    /// Patched onto the background shared with code blocks.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub inline_code: Style,
+    /// Gutter of blockquotes other than alerts, which are colored by their kind.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub blockquote: Style,
+    /// Thematic breaks, as well as the one above footnotes and link references.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub rule: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub table_border: Style,
+    /// Syntect theme highlighting code blocks, such as `InspiredGitHub` for light terminals.
+    /// Its background also shades inline code.
+    #[serde(deserialize_with = "deserialize_code_theme")]
+    pub code_theme: String,
+    /// Patched onto the background of the code theme.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub code_block_border: Style,
+    /// Alerts marked by e.g. `> [!NOTE]`, with their title in bold.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub note: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub tip: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub important: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub warning: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub caution: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub tab: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub focused_tab: Style,
+    /// Time of the last change to a file, next to its tab name.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub modified_tab: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub missing_tab: Style,
+    /// Patched onto the last contents of removed files.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub missing_document: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub selected_link: Style,
+    /// Entry of the section in view within the table of contents.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub current_heading: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub table_of_contents_border: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub status_success: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub status_error: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub search_match: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub current_search_match: Style,
+    /// Options toggled on while typing a search, such as `[regex]`.
+    #[serde(deserialize_with = "deserialize_style")]
+    pub search_option: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let style = Style::default();
+        Theme {
+            h1: style
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            h2: style.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            h3: style.fg(Color::Green).add_modifier(Modifier::BOLD),
+            h4: style.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            h5: style.fg(Color::Blue),
+            h6: style.fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            link: style.fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            link_reference: style.fg(Color::DarkGray),
+            list_marker: style.fg(Color::Yellow),
+            footnote_marker: style.fg(Color::Cyan),
+            inline_code: style,
+            blockquote: style.fg(Color::DarkGray),
+            rule: style.fg(Color::DarkGray),
+            table_border: style.fg(Color::DarkGray),
+            code_theme: "base16-ocean.dark".to_string(),
+            code_block_border: style.fg(Color::DarkGray),
+            note: style.fg(Color::Blue),
+            tip: style.fg(Color::Green),
+            important: style.fg(Color::Magenta),
+            warning: style.fg(Color::Yellow),
+            caution: style.fg(Color::Red),
+            tab: style.fg(Color::DarkGray),
+            focused_tab: style,
+            modified_tab: style.fg(Color::Yellow),
+            missing_tab: style.fg(Color::Red),
+            missing_document: style.fg(Color::DarkGray),
+            selected_link: style.add_modifier(Modifier::REVERSED),
+            current_heading: style.add_modifier(Modifier::REVERSED),
+            table_of_contents_border: style.fg(Color::DarkGray),
+            status_success: style.fg(Color::Green),
+            status_error: style.fg(Color::Red),
+            search_match: style
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .remove_modifier(Modifier::UNDERLINED),
+            current_search_match: style
+                .fg(Color::Black)
+                .bg(Color::LightRed)
+                .remove_modifier(Modifier::UNDERLINED),
+            search_option: style.fg(Color::DarkGray),
+        }
+    }
+}
+
+impl Theme {
+    /// Headings decrease in prominence with each level by default, H1 being the most prominent.
+    pub fn heading(&self, level: HeadingLevel) -> Style {
+        match level {
+            HeadingLevel::H1 => self.h1,
+            HeadingLevel::H2 => self.h2,
+            HeadingLevel::H3 => self.h3,
+            HeadingLevel::H4 => self.h4,
+            HeadingLevel::H5 => self.h5,
+            HeadingLevel::H6 => self.h6,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StyleConfig {
+    fg: Option<ThemeColor>,
+    bg: Option<ThemeColor>,
+    #[serde(default)]
+    modifiers: Vec<ThemeModifier>,
+}
+
+fn deserialize_style<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Style, D::Error> {
+    let StyleConfig { fg, bg, modifiers } = StyleConfig::deserialize(deserializer)?;
+    let mut style = Style::default();
+    if let Some(ThemeColor(color)) = fg {
+        style = style.fg(color);
+    }
+    if let Some(ThemeColor(color)) = bg {
+        style = style.bg(color);
+    }
+    for modifier in modifiers {
+        style = style.add_modifier(modifier.modifier());
+    }
+    Ok(style)
+}
+
+fn deserialize_code_theme<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let code_theme = String::deserialize(deserializer)?;
+    if code_theme_names().any(|code_theme_name| code_theme_name == code_theme) {
+        return Ok(code_theme);
+    }
+    let mut code_theme_names: Vec<&str> = code_theme_names().collect();
+    code_theme_names.sort_unstable();
+    Err(de::Error::custom(format!(
+        "unknown code theme `{code_theme}`, expected one of `{}`",
+        code_theme_names.join("`, `")
+    )))
+}
+
+/// Named color such as `dark_gray`, `#rrggbb` hex code, or index into the 256 color palette.
+struct ThemeColor(Color);
+
+impl<'de> Deserialize<'de> for ThemeColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ThemeColorVisitor)
+    }
+}
+
+struct ThemeColorVisitor;
+
+impl<'de> Visitor<'de> for ThemeColorVisitor {
+    type Value = ThemeColor;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a color name such as `dark_gray`, a `#rrggbb` hex code or 0-255")
+    }
+
+    fn visit_i64<E: de::Error>(self, index: i64) -> Result<Self::Value, E> {
+        u8::try_from(index)
+            .map(|index| ThemeColor(Color::Indexed(index)))
+            .map_err(|_| E::invalid_value(de::Unexpected::Signed(index), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, color: &str) -> Result<Self::Value, E> {
+        if let Some(hex_code) = color.strip_prefix('#') {
+            let channel = |channel_index: usize| {
+                hex_code
+                    .get(channel_index * 2..channel_index * 2 + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+            return match (hex_code.len(), channel(0), channel(1), channel(2)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(ThemeColor(Color::Rgb(r, g, b))),
+                _ => Err(E::invalid_value(de::Unexpected::Str(color), &self)),
+            };
+        }
+        // Written in snake case, though any case and separators are accepted.
+        let color_name: String = color
+            .chars()
+            .filter(|ch| !matches!(ch, '_' | '-' | ' '))
+            .map(|ch| ch.to_ascii_lowercase())
+            .collect();
+        let named_color = match color_name.replace("grey", "gray").as_str() {
+            "reset" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" => Color::Gray,
+            "darkgray" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            "white" => Color::White,
+            _ => return Err(E::invalid_value(de::Unexpected::Str(color), &self)),
+        };
+        Ok(ThemeColor(named_color))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ThemeModifier {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reversed,
+    Hidden,
+    CrossedOut,
+}
+
+impl ThemeModifier {
+    fn modifier(self) -> Modifier {
+        match self {
+            ThemeModifier::Bold => Modifier::BOLD,
+            ThemeModifier::Dim => Modifier::DIM,
+            ThemeModifier::Italic => Modifier::ITALIC,
+            ThemeModifier::Underlined => Modifier::UNDERLINED,
+            ThemeModifier::SlowBlink => Modifier::SLOW_BLINK,
+            ThemeModifier::RapidBlink => Modifier::RAPID_BLINK,
+            ThemeModifier::Reversed => Modifier::REVERSED,
+            ThemeModifier::Hidden => Modifier::HIDDEN,
+            ThemeModifier::CrossedOut => Modifier::CROSSED_OUT,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::theme::Theme, markdown_renderer::html::render_html};
+
+/// Exports the one document given, be it a file or markdown read from stdin.
+pub fn export_html(
+    file_paths: &[PathBuf],
+    stdin_contents: Option<&str>,
+    html_path: &Path,
+    theme: &Theme,
+) -> Result<()> {
+    match (file_paths, stdin_contents) {
+        ([file_path], None) => {
+            write_html(&fs::read_to_string(file_path)?, file_path, html_path, theme)
+        }
+        ([], Some(stdin_contents)) => {
+            write_html(stdin_contents, Path::new("stdin"), html_path, theme)
+        }
+        _ => Err(anyhow!(
+            "Only a single document can be exported to HTML at a time."
+        )),
+    }
+}
+
+/// Writes the markdown as a HTML page, titled by the name of its file.
+pub fn write_html(
+    markdown_str: &str,
+    markdown_path: &Path,
+    html_path: &Path,
+    theme: &Theme,
+) -> Result<()> {
+    let title = markdown_path
+        .file_name()
+        .map_or_else(|| "stdin".into(), |file_name| file_name.to_string_lossy());
+    fs::write(html_path, render_html(markdown_str, &title, theme))?;
+    Ok(())
+}
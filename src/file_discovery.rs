@@ -0,0 +1,36 @@
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+/// Markdown files anywhere under the directory, sorted by path.
+/// Skips hidden files and those ignored by .gitignore and similar files.
+pub fn find_markdown_files(directory: &Path) -> Vec<PathBuf> {
+    let mut markdown_files: Vec<PathBuf> = walk(directory)
+        .filter(|path| path.is_file() && is_markdown_file(path))
+        .collect();
+    markdown_files.sort();
+    markdown_files
+}
+
+/// Directories searched for markdown files, the directory itself included,
+/// skipping the same hidden and ignored directories.
+pub fn find_searched_directories(directory: &Path) -> Vec<PathBuf> {
+    walk(directory).filter(|path| path.is_dir()).collect()
+}
+
+fn walk(directory: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(directory)
+        .build()
+        // Unreadable entries are skipped rather than failing the whole search.
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+}
+
+pub fn is_markdown_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        MARKDOWN_EXTENSIONS
+            .iter()
+            .any(|markdown_extension| extension.eq_ignore_ascii_case(markdown_extension))
+    })
+}
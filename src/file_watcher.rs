@@ -1,64 +1,379 @@
 use crate::{
     application::UpdateView,
+    file_discovery::{find_markdown_files, find_searched_directories, is_markdown_file},
     thread_helpers::{send_command, send_error_command},
     Command,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use notify::{
-    event::{AccessKind, AccessMode},
-    EventKind, RecommendedWatcher, Watcher,
+    event::{AccessKind, AccessMode, ModifyKind},
+    Event, EventKind, RecommendedWatcher, Watcher,
 };
 use std::{
-    path::PathBuf,
-    sync::mpsc::{self, Sender},
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
 };
 
-pub fn filewatcher(file_paths: Vec<PathBuf>) -> impl FnOnce(Sender<Result<Command>>) {
+// Editors saving a file may generate a burst of events, e.g. by writing to a temporary
+// file and renaming it over the original. Events are gathered until there has been
+// a pause this long, so that each save results in a single reload.
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(50);
+// Files written to continuously, such as logs, would otherwise delay reloads indefinitely.
+const MAX_DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
+const MISSING_FILE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub enum WatcherMessage {
+    Changed(notify::Result<Event>),
+    // Files opened after start up, such as by following links.
+    Watch(PathBuf),
+    // Files whose tabs have been closed.
+    Unwatch(PathBuf),
+}
+
+pub fn filewatcher(
+    file_paths: Vec<PathBuf>,
+    directories: Vec<PathBuf>,
+    grace_period: Duration,
+    watcher_sender: Sender<WatcherMessage>,
+    watcher_reciever: Receiver<WatcherMessage>,
+) -> impl FnOnce(Sender<Result<Command>>) {
     move |cmd_sender: Sender<anyhow::Result<Command>>| {
-        let (file_change_sender, file_change_reciever) = mpsc::channel();
-        if let Err(watch_error) = RecommendedWatcher::new(
-            file_change_sender,
-            notify::Config::default(),
-        )
-        .map(|mut watcher| -> Result<()> {
-            for file_path in &file_paths {
-                watcher.watch(file_path, notify::RecursiveMode::NonRecursive)?
+        let event_handler = move |notify_event| {
+            // Only fails once the watcher has stopped, which is reported on its own.
+            watcher_sender
+                .send(WatcherMessage::Changed(notify_event))
+                .ok();
+        };
+        let watch_result = RecommendedWatcher::new(event_handler, notify::Config::default())
+            .map_err(anyhow::Error::from)
+            .and_then(|watcher| {
+                watch_files(
+                    watcher,
+                    file_paths,
+                    directories,
+                    grace_period,
+                    &watcher_reciever,
+                    &cmd_sender,
+                )
+            });
+        if let Err(watch_error) = watch_result {
+            send_error_command(&cmd_sender, watch_error);
+        }
+    }
+}
+
+/// Sends reloads and removals of the files as they change, until the watcher fails.
+/// Removed files are reported missing at first, and only given up on once
+/// they have been missing for the grace period. Markdown files created
+/// under the directories are added as they appear, and files opened
+/// by the application are watched from then on, until closed.
+/// Directories that can't be watched are warned about and left out.
+fn watch_files(
+    mut watcher: RecommendedWatcher,
+    mut file_paths: Vec<PathBuf>,
+    directories: Vec<PathBuf>,
+    grace_period: Duration,
+    watcher_reciever: &Receiver<WatcherMessage>,
+    cmd_sender: &Sender<Result<Command>>,
+) -> Result<()> {
+    // Only the directories searched for markdown files are watched, each on its own,
+    // as recursive watches would include ignored ones such as `.git` or `target`.
+    let is_under_directories = |path: &Path| {
+        directories
+            .iter()
+            .any(|directory| path.starts_with(directory))
+    };
+    let mut searched_directories: BTreeSet<PathBuf> = BTreeSet::new();
+    for directory in &directories {
+        searched_directories.extend(watch_directories(
+            &mut watcher,
+            find_searched_directories(directory),
+            cmd_sender,
+        ));
+    }
+    // Watching parent directories rather than the files themselves, as
+    // watches on files are lost when they are replaced by a new file.
+    let parent_directories: BTreeSet<PathBuf> = file_paths
+        .iter()
+        .filter_map(|file_path| file_path.parent().map(PathBuf::from))
+        .filter(|parent_directory| !searched_directories.contains(parent_directory))
+        .collect();
+    let mut parent_directories: BTreeSet<PathBuf> =
+        watch_directories(&mut watcher, parent_directories, cmd_sender)
+            .into_iter()
+            .collect();
+    let mut missing_files: BTreeMap<PathBuf, Instant> = BTreeMap::new();
+    // Files closed by the application, which aren't to be added back from the directories.
+    let mut closed_files: BTreeSet<PathBuf> = BTreeSet::new();
+    loop {
+        // Missing files are checked for periodically, as they may reappear
+        // without notice, e.g. along with their unwatched parent directory.
+        let timeout = (!missing_files.is_empty()).then_some(MISSING_FILE_POLL_INTERVAL);
+        // Files themselves, the directories they're in, and new entries under the directories.
+        let is_tracked = |changed_path: &Path| {
+            file_paths
+                .iter()
+                .any(|file_path| file_path.starts_with(changed_path))
+                || searched_directories.contains(changed_path)
+                || (is_under_directories(changed_path)
+                    && (changed_path.is_dir() || is_markdown_file(changed_path)))
+        };
+        let (mut changed_paths, opened_files, unwatched_files) =
+            recv_debounced(watcher_reciever, timeout, is_tracked)?;
+        for unwatched_file in unwatched_files {
+            file_paths.retain(|file_path| *file_path != unwatched_file);
+            missing_files.remove(&unwatched_file);
+            closed_files.insert(unwatched_file);
+        }
+        changed_paths.extend(missing_files.keys().cloned());
+        for opened_file in opened_files {
+            closed_files.remove(&opened_file);
+            if file_paths.contains(&opened_file) {
+                continue;
             }
-            let mut file_paths = file_paths;
-            loop {
-                let notify_event = file_change_reciever.recv()??;
-                match notify_event.kind {
-                    EventKind::Access(AccessKind::Close(AccessMode::Write)) => send_command(
-                        &cmd_sender,
-                        Command::Update(UpdateView::Reload(notify_event.paths)),
-                    ),
-                    _ => {
-                        // General case for removals and moves.
-                        // Tracking moved separately might be done by watching root folder.
+            if let Some(parent_directory) = opened_file.parent().map(PathBuf::from) {
+                if !searched_directories.contains(&parent_directory)
+                    && !parent_directories.contains(&parent_directory)
+                {
+                    parent_directories.extend(watch_directories(
+                        &mut watcher,
+                        [parent_directory],
+                        cmd_sender,
+                    ));
+                }
+            }
+            file_paths.push(opened_file);
+        }
 
-                        // Multiple file deletions can occur between each notify remove event.
-                        // We don't want to attempt to render files which don't exists.
-                        // IMPROVEMENT: replace with drain filter once it becomes stable.
-                        // let removed_files = file_paths.drain_filter(|file| !file.exists())
-                        let mut removed_files: Vec<PathBuf> = Vec::new();
-                        for i in (0..file_paths.len()).rev() {
-                            if !file_paths[i].exists() {
-                                removed_files.push(file_paths.swap_remove(i));
-                            }
-                        }
-                        // Don't rerender the view from a new remove notification when the
-                        // file removal was handled in the previous send_command.
-                        if !removed_files.is_empty() {
-                            send_command(
-                                &cmd_sender,
-                                Command::Update(UpdateView::Remove(removed_files)),
-                            )
-                        }
-                    }
+        let mut updated_files: Vec<PathBuf> = Vec::new();
+        let mut newly_missing_files: Vec<PathBuf> = Vec::new();
+        let mut revived_directories: Vec<PathBuf> = Vec::new();
+        // Checking the files after the burst has settled, as they
+        // may well be removed and recreated within the same save.
+        for file_path in file_paths
+            .iter()
+            .filter(|file_path| changed_paths.contains(*file_path))
+        {
+            if file_path.exists() {
+                if missing_files.remove(file_path).is_some() {
+                    revived_directories.extend(file_path.parent().map(PathBuf::from));
                 }
+                updated_files.push(file_path.clone());
+            } else if !missing_files.contains_key(file_path) {
+                missing_files.insert(file_path.clone(), Instant::now());
+                newly_missing_files.push(file_path.clone());
             }
-        }) {
-            send_error_command(&cmd_sender, watch_error);
         }
+        // Searching anew rather than checking the created paths themselves, as whole
+        // directories may be moved in, and new files may well be git ignored.
+        let mut added_files: Vec<PathBuf> = Vec::new();
+        for directory in &directories {
+            let has_new_entries = changed_paths.iter().any(|changed_path| {
+                changed_path.starts_with(directory)
+                    && !file_paths.contains(changed_path)
+                    && (changed_path.is_dir() || is_markdown_file(changed_path))
+            });
+            if !has_new_entries {
+                continue;
+            }
+            let new_directories: Vec<PathBuf> = find_searched_directories(directory)
+                .into_iter()
+                .filter(|searched_directory| !searched_directories.contains(searched_directory))
+                .collect();
+            searched_directories.extend(watch_directories(
+                &mut watcher,
+                new_directories,
+                cmd_sender,
+            ));
+            for markdown_file in find_markdown_files(directory) {
+                if !file_paths.contains(&markdown_file)
+                    && !added_files.contains(&markdown_file)
+                    && !closed_files.contains(&markdown_file)
+                {
+                    added_files.push(markdown_file);
+                }
+            }
+        }
+        file_paths.extend(added_files.iter().cloned());
+        // Watches are lost along with directories replaced as a whole, as done by
+        // some version control operations, and have to be established anew.
+        changed_paths.extend(revived_directories);
+        for parent_directory in parent_directories.intersection(&changed_paths) {
+            // Unwatching fails if the watch is already gone, which is fine.
+            watcher.unwatch(parent_directory).ok();
+            if parent_directory.exists() {
+                watch_directories(&mut watcher, [parent_directory.clone()], cmd_sender);
+            }
+        }
+        let changed_directories: Vec<PathBuf> = searched_directories
+            .intersection(&changed_paths)
+            .cloned()
+            .collect();
+        for changed_directory in changed_directories {
+            watcher.unwatch(&changed_directory).ok();
+            searched_directories.remove(&changed_directory);
+            // Removed directories are watched again once found anew.
+            if changed_directory.exists() {
+                searched_directories.extend(watch_directories(
+                    &mut watcher,
+                    [changed_directory],
+                    cmd_sender,
+                ));
+            }
+        }
+        let removed_files: Vec<PathBuf> = missing_files
+            .iter()
+            .filter(|(_, missing_since)| missing_since.elapsed() >= grace_period)
+            .map(|(file_path, _)| file_path.clone())
+            .collect();
+        for removed_file in &removed_files {
+            missing_files.remove(removed_file);
+        }
+        file_paths.retain(|file_path| !removed_files.contains(file_path));
+
+        if !added_files.is_empty() {
+            send_command(cmd_sender, Command::Update(UpdateView::Add(added_files)))
+        }
+        if !updated_files.is_empty() {
+            send_command(
+                cmd_sender,
+                Command::Update(UpdateView::Reload(updated_files)),
+            )
+        }
+        if !newly_missing_files.is_empty() {
+            send_command(
+                cmd_sender,
+                Command::Update(UpdateView::Missing(newly_missing_files)),
+            )
+        }
+        if !removed_files.is_empty() {
+            send_command(
+                cmd_sender,
+                Command::Update(UpdateView::Remove(removed_files)),
+            )
+        }
+    }
+}
+
+/// Watches the entries of each directory, returning the directories watched. Failing
+/// to watch some, such as once the system limit on watches has been reached, is only
+/// warned about, as the rest can still be watched.
+fn watch_directories(
+    watcher: &mut RecommendedWatcher,
+    directories: impl IntoIterator<Item = PathBuf>,
+    cmd_sender: &Sender<Result<Command>>,
+) -> Vec<PathBuf> {
+    let mut watched_directories = Vec::new();
+    let mut unwatched_directories = Vec::new();
+    let mut watch_error = None;
+    for directory in directories {
+        match watcher.watch(&directory, notify::RecursiveMode::NonRecursive) {
+            Ok(()) => watched_directories.push(directory),
+            Err(error) => {
+                watch_error.get_or_insert(error);
+                unwatched_directories.push(directory);
+            }
+        }
+    }
+    if let (Some(watch_error), Some(unwatched_directory)) =
+        (watch_error, unwatched_directories.first())
+    {
+        let other_directories = match unwatched_directories.len() - 1 {
+            0 => String::new(),
+            1 => " and 1 other directory".to_string(),
+            count => format!(" and {count} other directories"),
+        };
+        send_command(
+            cmd_sender,
+            Command::Update(UpdateView::Warning(format!(
+                "Not watching {}{other_directories} for changes: {watch_error}",
+                unwatched_directory.display()
+            ))),
+        );
+    }
+    watched_directories
+}
+
+/// Blocks until a burst of changes to tracked paths has settled, returning the paths
+/// that changed along with any files to start and stop watching, or until `timeout`
+/// has passed without any changes, returning none. Bursts are cut short once they
+/// have lasted for `MAX_DEBOUNCE_DURATION`.
+fn recv_debounced(
+    watcher_reciever: &Receiver<WatcherMessage>,
+    timeout: Option<Duration>,
+    is_tracked: impl Fn(&Path) -> bool,
+) -> Result<(BTreeSet<PathBuf>, Vec<PathBuf>, Vec<PathBuf>)> {
+    let wait_deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut changed_paths = BTreeSet::new();
+    let mut opened_files = Vec::new();
+    let mut unwatched_files = Vec::new();
+    // When the current burst started, and when it last got a change.
+    let mut burst: Option<(Instant, Instant)> = None;
+    loop {
+        let recv_deadline = match burst {
+            Some((started_at, last_changed_at)) => {
+                Some((last_changed_at + DEBOUNCE_DURATION).min(started_at + MAX_DEBOUNCE_DURATION))
+            }
+            None => wait_deadline,
+        };
+        let received = match recv_deadline {
+            // Checked up front, as untracked changes may keep arriving past the deadline.
+            Some(recv_deadline) if recv_deadline <= Instant::now() => {
+                Err(RecvTimeoutError::Timeout)
+            }
+            Some(recv_deadline) => watcher_reciever
+                .recv_timeout(recv_deadline.saturating_duration_since(Instant::now())),
+            None => watcher_reciever
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let burst_changed = match received {
+            Ok(WatcherMessage::Changed(notify_event)) => {
+                let notify_event = notify_event?;
+                // Reads or metadata changes alone don't end the wait.
+                if !is_content_change(&notify_event.kind) {
+                    continue;
+                }
+                let tracked_paths: Vec<PathBuf> = notify_event
+                    .paths
+                    .into_iter()
+                    .filter(|changed_path| is_tracked(changed_path))
+                    .collect();
+                let has_tracked_paths = !tracked_paths.is_empty();
+                changed_paths.extend(tracked_paths);
+                has_tracked_paths
+            }
+            Ok(WatcherMessage::Watch(file_path)) => {
+                opened_files.push(file_path);
+                true
+            }
+            Ok(WatcherMessage::Unwatch(file_path)) => {
+                unwatched_files.push(file_path);
+                true
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                return Ok((changed_paths, opened_files, unwatched_files))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(anyhow!("File watcher stopped unexpectedly."))
+            }
+        };
+        if burst_changed {
+            let now = Instant::now();
+            burst = Some((burst.map_or(now, |(started_at, _)| started_at), now));
+        }
+    }
+}
+
+/// Changes that may alter what a file renders to. Reads, such as
+/// those made by grow itself when rendering, are left out.
+fn is_content_change(event_kind: &EventKind) -> bool {
+    match event_kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => true,
+        _ => false,
     }
 }
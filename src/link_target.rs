@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
+
+use crate::file_discovery::is_markdown_file;
+
+#[derive(Debug, PartialEq)]
+pub enum LinkTarget {
+    // Anchors within the linking document itself, which may have no file, e.g. if read from stdin.
+    Anchor(String),
+    Document {
+        file_path: PathBuf,
+        anchor: Option<String>,
+    },
+    // URLs, or paths to files other than markdown files.
+    External(String),
+}
+
+/// Resolves link destinations relative to the directory of the linking file.
+/// Links to markdown files, or to anchors within the linking document itself,
+/// are followed within the application.
+pub fn link_target(linking_file_path: &Path, url: &str) -> LinkTarget {
+    if has_url_scheme(url) {
+        return LinkTarget::External(url.to_string());
+    }
+    let (path, anchor) = match url.split_once('#') {
+        Some((path, anchor)) => (path, Some(percent_decoded(anchor))),
+        None => (url, None),
+    };
+    if path.is_empty() {
+        return LinkTarget::Anchor(anchor.unwrap_or_default());
+    }
+    let file_path = linking_file_path
+        .parent()
+        .unwrap_or(Path::new("/"))
+        .join(percent_decoded(path));
+    if is_markdown_file(&file_path) {
+        LinkTarget::Document { file_path, anchor }
+    } else {
+        LinkTarget::External(file_path.to_string_lossy().into_owned())
+    }
+}
+
+/// Whether the URL starts with a scheme, such as `https:` or `mailto:`.
+/// Single letter schemes are left out, as they're rather Windows drive letters.
+fn has_url_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+    })
+}
+
+/// Decodes `%20` style escapes, leaving malformed ones as they are.
+fn percent_decoded(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut remaining = text.as_bytes();
+    while let Some((&byte, rest)) = remaining.split_first() {
+        let escaped_byte = rest
+            .get(..2)
+            .and_then(|hex_digits| std::str::from_utf8(hex_digits).ok())
+            .and_then(|hex_digits| u8::from_str_radix(hex_digits, 16).ok());
+        match escaped_byte {
+            Some(escaped_byte) if byte == b'%' => {
+                bytes.push(escaped_byte);
+                remaining = &rest[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                remaining = rest;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
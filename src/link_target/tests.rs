@@ -0,0 +1,63 @@
+use crate::link_target::{has_url_scheme, link_target, percent_decoded, LinkTarget};
+use std::path::{Path, PathBuf};
+
+const LINKING_FILE_PATH: &str = "/docs/guide/readme.md";
+
+fn document(file_path: &str, anchor: Option<&str>) -> LinkTarget {
+    LinkTarget::Document {
+        file_path: PathBuf::from(file_path),
+        anchor: anchor.map(String::from),
+    }
+}
+
+#[test]
+fn relative_links_resolve_from_the_linking_file() {
+    assert_eq!(
+        link_target(Path::new(LINKING_FILE_PATH), "../x.md#a"),
+        document("/docs/guide/../x.md", Some("a"))
+    );
+    assert_eq!(
+        link_target(Path::new("-"), "#usage"),
+        LinkTarget::Anchor("usage".to_string())
+    );
+    assert_eq!(
+        link_target(Path::new(LINKING_FILE_PATH), "diagram.png"),
+        LinkTarget::External("/docs/guide/diagram.png".to_string())
+    );
+}
+
+#[test]
+fn links_are_percent_decoded() {
+    assert_eq!(
+        link_target(
+            Path::new(LINKING_FILE_PATH),
+            "my%20notes.md#getting%20started"
+        ),
+        document("/docs/guide/my notes.md", Some("getting started"))
+    );
+    assert_eq!(percent_decoded("100%"), "100%");
+    assert_eq!(percent_decoded("%e2%9c%93"), "✓");
+}
+
+#[test]
+fn urls_with_schemes_are_external() {
+    for url in [
+        "mailto:foo@bar.com",
+        "https://example.com/a.md",
+        "http://www.example.com",
+    ] {
+        assert_eq!(
+            link_target(Path::new(LINKING_FILE_PATH), url),
+            LinkTarget::External(url.to_string())
+        );
+    }
+}
+
+#[test]
+fn windows_drive_letters_are_not_url_schemes() {
+    assert!(!has_url_scheme("C:\\notes\\todo.md"));
+    assert!(matches!(
+        link_target(Path::new(LINKING_FILE_PATH), "C:\\notes\\todo.md"),
+        LinkTarget::Document { .. }
+    ));
+}
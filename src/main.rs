@@ -1,14 +1,24 @@
 mod application;
 mod args;
+mod config;
+mod export;
+mod file_discovery;
 mod file_watcher;
+mod link_target;
 mod markdown_renderer;
+mod print;
+mod search;
+mod signal_handler;
 mod term_event_handler;
+mod terminal_guard;
 mod thread_helpers;
 
 use anyhow::Result;
 use application::ClosedApplication;
+use config::Config;
 use file_watcher::filewatcher;
-use std::{io::Write, process::ExitCode, sync::mpsc};
+use signal_handler::signal_handler;
+use std::{io::Write, process::ExitCode, sync::mpsc, time::Duration};
 use term_event_handler::event_loop;
 
 fn main() -> ExitCode {
@@ -25,12 +35,52 @@ fn main() -> ExitCode {
 
 fn run_application() -> Result<()> {
     let args = args::parse()?;
+    let Config {
+        keymap,
+        theme,
+        behaviour,
+    } = Config::load(args.config.as_deref())?;
+    let behaviour = args.override_behaviour(behaviour);
+    if let Some(html_path) = &args.export_html {
+        return export::export_html(
+            &args.files,
+            args.stdin_contents.as_deref(),
+            html_path,
+            &theme,
+        );
+    }
+    if args.print {
+        return print::print_files(
+            &args.files,
+            args.stdin_contents.as_deref(),
+            args.width,
+            behaviour.wrap_width,
+            &theme,
+        );
+    }
     let (cmd_sender, command_reciever) = mpsc::channel();
+    let (watcher_sender, watcher_reciever) = mpsc::channel();
     thread_helpers::spawn_threads(
         cmd_sender,
-        thread_closures!(event_loop, filewatcher(args.files.clone())),
+        thread_closures!(
+            event_loop(keymap, behaviour.mouse_scroll_lines),
+            signal_handler,
+            filewatcher(
+                args.files.clone(),
+                args.directories.clone(),
+                Duration::from_secs(behaviour.grace_period),
+                watcher_sender.clone(),
+                watcher_reciever
+            )
+        ),
+    )?;
+    let mut application = ClosedApplication::open(
+        args.files,
+        args.stdin_contents,
+        behaviour,
+        theme,
+        watcher_sender,
     )?;
-    let mut application = ClosedApplication::open(args.files)?;
     loop {
         match command_reciever.recv().unwrap() {
             Ok(command) => match command {
@@ -38,9 +88,33 @@ fn run_application() -> Result<()> {
                     application.close()?;
                     break;
                 }
+                Command::Suspend => application.suspend()?,
                 Command::NextView => application.select_next_view()?,
                 Command::PrevView => application.select_prev_view()?,
                 Command::Scroll(steps) => application.scroll_markdown_view(steps)?,
+                Command::ScrollHalfPage(half_pages) => {
+                    application.scroll_markdown_view_pages(half_pages, 2)?
+                }
+                Command::ScrollPage(pages) => application.scroll_markdown_view_pages(pages, 1)?,
+                Command::ScrollToTop => application.scroll_markdown_view_to(0)?,
+                Command::ScrollToBottom => application.scroll_markdown_view_to(usize::MAX)?,
+                Command::ToggleTableOfContents => application.toggle_table_of_contents()?,
+                Command::ExportHtml => application.export_html()?,
+                Command::NextHeading => application.jump_to_heading(false)?,
+                Command::PrevHeading => application.jump_to_heading(true)?,
+                Command::Search(search_command) => application.search(search_command)?,
+                Command::NextLink => application.select_link(false)?,
+                Command::PrevLink => application.select_link(true)?,
+                Command::FollowLink => application.follow_link()?,
+                Command::HistoryBack => application.go_through_history(true)?,
+                Command::HistoryForward => application.go_through_history(false)?,
+                Command::Click(column, row) => application.click(column, row)?,
+                Command::MiddleClick(column, row) => {
+                    if let Some(Command::Close) = application.close_tab_at(column, row)? {
+                        application.close()?;
+                        break;
+                    }
+                }
                 Command::Update(update) => {
                     let found_command_response = application.update_view(update)?;
                     if let Some(Command::Close) = found_command_response {
@@ -62,8 +136,26 @@ fn run_application() -> Result<()> {
 
 pub enum Command {
     Close,
+    Suspend,
     Update(application::UpdateView),
     NextView,
     PrevView,
     Scroll(i16),
+    ScrollHalfPage(i16),
+    ScrollPage(i16),
+    ScrollToTop,
+    ScrollToBottom,
+    Search(search::SearchCommand),
+    ToggleTableOfContents,
+    ExportHtml,
+    NextHeading,
+    PrevHeading,
+    NextLink,
+    PrevLink,
+    FollowLink,
+    HistoryBack,
+    HistoryForward,
+    // Column and row of mouse clicks.
+    Click(u16, u16),
+    MiddleClick(u16, u16),
 }
@@ -0,0 +1,57 @@
+use crate::config::theme::Theme;
+use ratatui::style::Style;
+
+/// GitHub alert blockquotes, marked by e.g. `> [!NOTE]` on their first line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AlertKind {
+    pub fn parse(alert_marker: &str) -> Option<Self> {
+        let kind = alert_marker.strip_prefix("[!")?.strip_suffix(']')?;
+        match kind.to_ascii_uppercase().as_str() {
+            "NOTE" => Some(AlertKind::Note),
+            "TIP" => Some(AlertKind::Tip),
+            "IMPORTANT" => Some(AlertKind::Important),
+            "WARNING" => Some(AlertKind::Warning),
+            "CAUTION" => Some(AlertKind::Caution),
+            _ => None,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            AlertKind::Note => "Note",
+            AlertKind::Tip => "Tip",
+            AlertKind::Important => "Important",
+            AlertKind::Warning => "Warning",
+            AlertKind::Caution => "Caution",
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            AlertKind::Note => "ℹ",
+            AlertKind::Tip => "✱",
+            AlertKind::Important => "✉",
+            AlertKind::Warning => "⚠",
+            AlertKind::Caution => "⊘",
+        }
+    }
+
+    /// Style of the gutter of the alert, its title being bold on top of it.
+    pub fn style(self, theme: &Theme) -> Style {
+        match self {
+            AlertKind::Note => theme.note,
+            AlertKind::Tip => theme.tip,
+            AlertKind::Important => theme.important,
+            AlertKind::Warning => theme.warning,
+            AlertKind::Caution => theme.caution,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use super::wrap::split_at_width;
+use crate::config::theme::Theme;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+};
+use std::sync::OnceLock;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, Theme as HighlightingTheme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+use unicode_width::UnicodeWidthChar;
+
+const TAB_WIDTH: usize = 4;
+// Fence info strings for languages missing from the syntect defaults,
+// mapped to the closest available syntax.
+const LANGUAGE_ALIASES: [(&str, &str); 4] = [
+    ("toml", "properties"),
+    ("shell", "sh"),
+    ("console", "sh"),
+    ("jsonc", "json"),
+];
+
+/// Renders a code block verbatim inside a bordered and shaded box spanning `width`.
+/// Lines wider than the box are hard wrapped rather than trimmed.
+pub fn render_code_block(
+    code: &str,
+    language: Option<&str>,
+    width: u16,
+    theme: &Theme,
+) -> Vec<Spans<'static>> {
+    let (syntax_set, _) = highlighting_assets();
+    let highlighting_theme = highlighting_theme(theme);
+    let background_style = Style::default().bg(theme_background(highlighting_theme));
+    let border_style = background_style.patch(theme.code_block_border);
+    // Two columns on each side for the border and its padding.
+    let inner_width = (width as usize).saturating_sub(4).max(1);
+
+    let label = language
+        .map(|language| format!(" {language} "))
+        .unwrap_or_default();
+    let top_rule_width = (inner_width + 2).saturating_sub(label.chars().count() + 1);
+    let mut lines = vec![Spans::from(vec![
+        Span::styled("╭─", border_style),
+        Span::styled(label, border_style.add_modifier(Modifier::BOLD)),
+        Span::styled(format!("{}╮", "─".repeat(top_rule_width)), border_style),
+    ])];
+
+    for highlighted_line in highlight_code(syntax_set, highlighting_theme, code, language) {
+        for mut line_chunk in split_at_width(highlighted_line, inner_width) {
+            let chunk_width: usize = line_chunk.iter().map(Span::width).sum();
+            line_chunk.insert(0, Span::styled("│ ", border_style));
+            line_chunk.push(Span::styled(
+                " ".repeat(inner_width.saturating_sub(chunk_width)),
+                background_style,
+            ));
+            line_chunk.push(Span::styled(" │", border_style));
+            lines.push(Spans::from(line_chunk));
+        }
+    }
+
+    lines.push(Spans::from(Span::styled(
+        format!("╰{}╯", "─".repeat(inner_width + 2)),
+        border_style,
+    )));
+    lines
+}
+
+/// Background shared by code blocks and inline code.
+pub fn code_background(theme: &Theme) -> Color {
+    theme_background(highlighting_theme(theme))
+}
+
+/// Highlighted lines of code as within code blocks, yet without their box.
+pub fn highlight_code_lines(
+    code: &str,
+    language: Option<&str>,
+    theme: &Theme,
+) -> Vec<Vec<Span<'static>>> {
+    let (syntax_set, _) = highlighting_assets();
+    highlight_code(syntax_set, highlighting_theme(theme), code, language)
+}
+
+/// Names of the syntect default themes, which code blocks may be highlighted with.
+pub fn code_theme_names() -> impl Iterator<Item = &'static str> {
+    highlighting_assets().1.themes.keys().map(String::as_str)
+}
+
+fn highlighting_assets() -> &'static (SyntaxSet, ThemeSet) {
+    // Loading the syntect defaults is too slow to redo on each render.
+    static ASSETS: OnceLock<(SyntaxSet, ThemeSet)> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        (
+            SyntaxSet::load_defaults_newlines(),
+            ThemeSet::load_defaults(),
+        )
+    })
+}
+
+fn highlighting_theme(theme: &Theme) -> &'static HighlightingTheme {
+    &highlighting_assets().1.themes[&theme.code_theme]
+}
+
+fn find_syntax<'a>(syntax_set: &'a SyntaxSet, language: Option<&str>) -> &'a SyntaxReference {
+    language
+        .and_then(|language| {
+            let language = LANGUAGE_ALIASES
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(language))
+                .map_or(language, |(_, target)| target);
+            syntax_set.find_syntax_by_token(language)
+        })
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text())
+}
+
+fn highlight_code(
+    syntax_set: &SyntaxSet,
+    theme: &HighlightingTheme,
+    code: &str,
+    language: Option<&str>,
+) -> Vec<Vec<Span<'static>>> {
+    let background = theme_background(theme);
+    let mut highlighter = HighlightLines::new(find_syntax(syntax_set, language), theme);
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let line = expand_tabs(line.trim_end_matches(['\n', '\r']));
+            match highlighter.highlight_line(&line, syntax_set) {
+                Ok(regions) => regions
+                    .into_iter()
+                    .map(|(syntect_style, text)| {
+                        Span::styled(
+                            text.to_string(),
+                            convert_style(syntect_style).bg(background),
+                        )
+                    })
+                    .collect(),
+                // Fall back to unhighlighted text rather than dropping the line.
+                Err(_) => vec![Span::styled(line, Style::default().bg(background))],
+            }
+        })
+        .collect()
+}
+
+/// Replaces tabs with spaces up to the next tab stop, keeping tab aligned code aligned.
+fn expand_tabs(line: &str) -> String {
+    let mut expanded_line = String::with_capacity(line.len());
+    let mut column = 0;
+    for ch in line.chars() {
+        if ch == '\t' {
+            let tab_width = TAB_WIDTH - column % TAB_WIDTH;
+            expanded_line.push_str(&" ".repeat(tab_width));
+            column += tab_width;
+        } else {
+            expanded_line.push(ch);
+            column += ch.width().unwrap_or(0);
+        }
+    }
+    expanded_line
+}
+
+fn convert_style(syntect_style: syntect::highlighting::Style) -> Style {
+    let foreground = syntect_style.foreground;
+    let mut style = Style::default().fg(Color::Rgb(foreground.r, foreground.g, foreground.b));
+    if syntect_style.font_style.contains(FontStyle::BOLD) {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if syntect_style.font_style.contains(FontStyle::ITALIC) {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if syntect_style.font_style.contains(FontStyle::UNDERLINE) {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    style
+}
+
+fn theme_background(theme: &HighlightingTheme) -> Color {
+    theme
+        .settings
+        .background
+        .map_or(Color::Black, |background| {
+            Color::Rgb(background.r, background.g, background.b)
+        })
+}
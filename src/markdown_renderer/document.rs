@@ -0,0 +1,524 @@
+use super::alert::AlertKind;
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, Event, HeadingLevel, LinkType, OffsetIter, Options, Parser, Tag,
+};
+use std::{collections::HashSet, iter::Peekable, ops::Range};
+
+const URL_PREFIXES: [&str; 3] = ["https://", "http://", "www."];
+
+/// Markdown document parsed into a tree of blocks, each knowing
+/// which lines of the markdown source it was parsed from.
+#[derive(Debug, PartialEq)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    /// Zero based, end exclusive, line numbers of the block in the markdown source.
+    pub source_lines: Range<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BlockKind {
+    Paragraph(Vec<Inline>),
+    Heading(HeadingLevel, Vec<Inline>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+    List {
+        start_number: Option<u64>,
+        /// Tight lists have no blank lines between their items.
+        tight: bool,
+        items: Vec<ListItem>,
+    },
+    BlockQuote {
+        alert: Option<AlertKind>,
+        blocks: Vec<Block>,
+    },
+    /// Rows of cells, the first row being the header.
+    Table {
+        alignments: Vec<Alignment>,
+        rows: Vec<Vec<Vec<Inline>>>,
+    },
+    FootnoteDefinition {
+        label: String,
+        blocks: Vec<Block>,
+    },
+    Rule,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ListItem {
+    /// Whether the task list item is checked, `None` for regular items.
+    pub task: Option<bool>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Code(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    Link {
+        url: String,
+        /// Autolinks are their own link text, be it `<https://...>` or a bare URL.
+        autolink: bool,
+        content: Vec<Inline>,
+    },
+    Image {
+        url: String,
+        alt: Vec<Inline>,
+    },
+    FootnoteReference(String),
+    SoftBreak,
+    HardBreak,
+}
+
+impl Inline {
+    /// Text content without any styling, such as that of a heading.
+    pub fn plain_text(inlines: &[Inline]) -> String {
+        let mut plain_text = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) | Inline::Code(text) => plain_text.push_str(text),
+                Inline::Emphasis(content)
+                | Inline::Strong(content)
+                | Inline::Strikethrough(content)
+                | Inline::Link { content, .. }
+                | Inline::Image { alt: content, .. } => {
+                    plain_text.push_str(&Inline::plain_text(content))
+                }
+                Inline::SoftBreak | Inline::HardBreak => plain_text.push(' '),
+                Inline::FootnoteReference(_) => (),
+            }
+        }
+        plain_text
+    }
+}
+
+/// Anchor of a heading as GitHub generates it: lowercased, spaces turned into
+/// hyphens, and punctuation other than hyphens and underscores dropped.
+pub fn heading_anchor(title: &str) -> String {
+    title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            ch if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Anchors given out to the headings of a document so far, in document order.
+#[derive(Default)]
+pub struct HeadingAnchors {
+    taken_anchors: HashSet<String>,
+}
+
+impl HeadingAnchors {
+    /// Anchors of headings sharing a title are told apart by a numbered suffix.
+    pub fn unique_anchor(&mut self, title: &str) -> String {
+        let anchor = heading_anchor(title);
+        let unique_anchor = (0..)
+            .map(|suffix| match suffix {
+                0 => anchor.clone(),
+                suffix => format!("{anchor}-{suffix}"),
+            })
+            .find(|unique_anchor| !self.taken_anchors.contains(unique_anchor))
+            .expect("Unbounded suffixes.");
+        self.taken_anchors.insert(unique_anchor.clone());
+        unique_anchor
+    }
+}
+
+/// Numbers given out to the footnotes of a document so far, counting from 1.
+#[derive(Default)]
+pub struct FootnoteNumbers {
+    labels: Vec<String>,
+}
+
+impl FootnoteNumbers {
+    /// Footnotes are numbered by first appearance of either their reference or definition.
+    pub fn number(&mut self, label: &str) -> usize {
+        match self
+            .labels
+            .iter()
+            .position(|footnote_label| footnote_label == label)
+        {
+            Some(index) => index + 1,
+            None => {
+                self.labels.push(label.to_string());
+                self.labels.len()
+            }
+        }
+    }
+}
+
+/// GitHub flavored markdown extensions supported by pulldown-cmark.
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+pub fn parse_document(markdown_str: &str) -> Document {
+    let mut document_parser = DocumentParser {
+        events: Parser::new_ext(markdown_str, parser_options())
+            .into_offset_iter()
+            .peekable(),
+        line_starts: markdown_str
+            .match_indices('\n')
+            .map(|(newline_index, _)| newline_index + 1)
+            .collect(),
+        task_marker: None,
+    };
+    Document {
+        blocks: document_parser.parse_blocks(),
+    }
+}
+
+struct DocumentParser<'a> {
+    events: Peekable<OffsetIter<'a, 'a>>,
+    // Byte offsets at which each line but the first starts.
+    line_starts: Vec<usize>,
+    // Task marker of the list item being parsed, which may be found within its first paragraph.
+    task_marker: Option<bool>,
+}
+
+impl<'a> DocumentParser<'a> {
+    /// Parses blocks until the end of their container, consuming its end tag.
+    fn parse_blocks(&mut self) -> Vec<Block> {
+        self.parse_container_blocks().0
+    }
+
+    /// Also returns whether any paragraphs were explicitly tagged, which is only
+    /// the case for the paragraphs of list items if the list is loose.
+    fn parse_container_blocks(&mut self) -> (Vec<Block>, bool) {
+        let mut blocks = Vec::new();
+        let mut tagged_paragraphs = false;
+        while let Some((event, _)) = self.events.peek() {
+            if is_inline(event) {
+                // Inline content directly within a tight list item.
+                let source_lines = self.peek_source_lines();
+                let inlines = self.parse_inlines();
+                blocks.push(Block {
+                    kind: BlockKind::Paragraph(inlines),
+                    source_lines,
+                });
+                continue;
+            }
+            let Some((event, byte_range)) = self.events.next() else {
+                break;
+            };
+            let source_lines = self.source_lines(byte_range);
+            let kind = match event {
+                Event::End(_) => break,
+                Event::Start(tag) => {
+                    tagged_paragraphs |= tag == Tag::Paragraph;
+                    match self.parse_block(tag) {
+                        Some(kind) => kind,
+                        None => continue,
+                    }
+                }
+                Event::Rule => BlockKind::Rule,
+                Event::TaskListMarker(checked) => {
+                    self.task_marker = Some(checked);
+                    continue;
+                }
+                // Such as HTML, which isn't rendered.
+                _ => continue,
+            };
+            blocks.push(Block { kind, source_lines });
+        }
+        (blocks, tagged_paragraphs)
+    }
+
+    fn parse_block(&mut self, tag: Tag<'a>) -> Option<BlockKind> {
+        let block_kind = match tag {
+            Tag::Paragraph => BlockKind::Paragraph(self.parse_inlines_until_end()),
+            Tag::Heading(level, _, _) => BlockKind::Heading(level, self.parse_inlines_until_end()),
+            Tag::CodeBlock(kind) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info_string) => {
+                        info_string.split_whitespace().next().map(str::to_string)
+                    }
+                    CodeBlockKind::Indented => None,
+                };
+                let mut code = String::new();
+                for (event, _) in self.events.by_ref() {
+                    match event {
+                        Event::Text(text) => code.push_str(&text),
+                        Event::End(_) => break,
+                        _ => (),
+                    }
+                }
+                BlockKind::CodeBlock { language, code }
+            }
+            Tag::List(start_number) => {
+                let mut items = Vec::new();
+                let mut tight = true;
+                while let Some((event, _)) = self.events.next() {
+                    match event {
+                        Event::Start(Tag::Item) => {
+                            // Set aside the task marker of any parent item.
+                            let parent_task_marker = self.task_marker.take();
+                            // Loose list item content is wrapped in paragraph tags,
+                            // unlike the bare inline content of tight list items.
+                            let (blocks, tagged_paragraphs) = self.parse_container_blocks();
+                            tight &= !tagged_paragraphs;
+                            let task = std::mem::replace(&mut self.task_marker, parent_task_marker);
+                            items.push(ListItem { task, blocks });
+                        }
+                        Event::End(_) => break,
+                        _ => (),
+                    }
+                }
+                BlockKind::List {
+                    start_number,
+                    tight,
+                    items,
+                }
+            }
+            Tag::BlockQuote => {
+                let mut blocks = self.parse_blocks();
+                let alert = take_alert_marker(&mut blocks);
+                BlockKind::BlockQuote { alert, blocks }
+            }
+            Tag::Table(alignments) => {
+                let mut rows = Vec::new();
+                while let Some((event, _)) = self.events.next() {
+                    match event {
+                        Event::Start(Tag::TableHead | Tag::TableRow) => rows.push(Vec::new()),
+                        Event::Start(Tag::TableCell) => {
+                            let cell = self.parse_inlines_until_end();
+                            if let Some(row) = rows.last_mut() {
+                                row.push(cell);
+                            }
+                        }
+                        Event::End(Tag::Table(_)) => break,
+                        _ => (),
+                    }
+                }
+                BlockKind::Table { alignments, rows }
+            }
+            Tag::FootnoteDefinition(label) => BlockKind::FootnoteDefinition {
+                label: label.to_string(),
+                blocks: self.parse_blocks(),
+            },
+            _ => {
+                self.skip_until_end();
+                return None;
+            }
+        };
+        Some(block_kind)
+    }
+
+    /// Parses a run of inline content, stopping before the first non-inline event.
+    fn parse_inlines(&mut self) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        while let Some((event, _)) = self.events.peek() {
+            if !is_inline(event) {
+                break;
+            }
+            let Some((event, _)) = self.events.next() else {
+                break;
+            };
+            self.parse_inline(event, &mut inlines);
+        }
+        inlines
+    }
+
+    /// Parses inline content until the end of its container, consuming its end tag.
+    fn parse_inlines_until_end(&mut self) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        while let Some((event, _)) = self.events.next() {
+            if let Event::End(_) = event {
+                break;
+            }
+            self.parse_inline(event, &mut inlines);
+        }
+        inlines
+    }
+
+    fn parse_inline(&mut self, event: Event<'a>, inlines: &mut Vec<Inline>) {
+        match event {
+            Event::Text(text) => push_text(inlines, &text),
+            Event::Code(code) => inlines.push(Inline::Code(code.to_string())),
+            Event::SoftBreak => inlines.push(Inline::SoftBreak),
+            Event::HardBreak => inlines.push(Inline::HardBreak),
+            Event::FootnoteReference(label) => {
+                inlines.push(Inline::FootnoteReference(label.to_string()))
+            }
+            // Leads the first paragraph of loose task list items.
+            Event::TaskListMarker(checked) => self.task_marker = Some(checked),
+            Event::Start(Tag::Emphasis) => {
+                inlines.push(Inline::Emphasis(self.parse_inlines_until_end()))
+            }
+            Event::Start(Tag::Strong) => {
+                inlines.push(Inline::Strong(self.parse_inlines_until_end()))
+            }
+            Event::Start(Tag::Strikethrough) => {
+                inlines.push(Inline::Strikethrough(self.parse_inlines_until_end()))
+            }
+            Event::Start(Tag::Link(link_type, url, _)) => {
+                let content = self.parse_link_content();
+                let url = match link_type {
+                    LinkType::Email => format!("mailto:{url}"),
+                    _ => url.to_string(),
+                };
+                inlines.push(Inline::Link {
+                    url,
+                    autolink: matches!(link_type, LinkType::Autolink | LinkType::Email),
+                    content,
+                })
+            }
+            Event::Start(Tag::Image(_, url, _)) => inlines.push(Inline::Image {
+                url: url.to_string(),
+                alt: self.parse_inlines_until_end(),
+            }),
+            _ => (),
+        }
+    }
+
+    /// Link text, in which bare URLs are not turned into nested links.
+    fn parse_link_content(&mut self) -> Vec<Inline> {
+        let mut content = Vec::new();
+        while let Some((event, _)) = self.events.next() {
+            match event {
+                Event::End(_) => break,
+                Event::Text(text) => content.push(Inline::Text(text.to_string())),
+                event => self.parse_inline(event, &mut content),
+            }
+        }
+        content
+    }
+
+    fn skip_until_end(&mut self) {
+        let mut depth = 0;
+        for (event, _) in self.events.by_ref() {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) if depth == 0 => break,
+                Event::End(_) => depth -= 1,
+                _ => (),
+            }
+        }
+    }
+
+    fn peek_source_lines(&mut self) -> Range<usize> {
+        let byte_range = self
+            .events
+            .peek()
+            .map(|(_, byte_range)| byte_range.clone())
+            .unwrap_or_default();
+        self.source_lines(byte_range)
+    }
+
+    fn source_lines(&self, byte_range: Range<usize>) -> Range<usize> {
+        let line_of = |byte_offset: usize| {
+            self.line_starts
+                .partition_point(|line_start| *line_start <= byte_offset)
+        };
+        let start_line = line_of(byte_range.start);
+        // The range may end just after a trailing newline.
+        let end_line = line_of(byte_range.end.saturating_sub(1).max(byte_range.start)) + 1;
+        start_line..end_line
+    }
+}
+
+fn is_inline(event: &Event) -> bool {
+    match event {
+        Event::Text(_)
+        | Event::Code(_)
+        | Event::SoftBreak
+        | Event::HardBreak
+        | Event::FootnoteReference(_) => true,
+        // Inline HTML tags are skipped, keeping the text between them, while
+        // lines of HTML blocks end in a newline and end the inline run.
+        Event::Html(html) => !html.ends_with('\n'),
+        Event::Start(tag) | Event::End(tag) => matches!(
+            tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..)
+        ),
+        _ => false,
+    }
+}
+
+/// Pushes text, turning any bare URLs within it into autolinks.
+fn push_text(inlines: &mut Vec<Inline>, text: &str) {
+    let mut plain_text = String::new();
+    for word in text.split_inclusive(char::is_whitespace) {
+        let url_length = bare_url_length(word);
+        if url_length == 0 {
+            plain_text.push_str(word);
+            continue;
+        }
+        if !plain_text.is_empty() {
+            inlines.push(Inline::Text(std::mem::take(&mut plain_text)));
+        }
+        let (url, rest) = word.split_at(url_length);
+        // Bare `www.` links are shown as written, though linking over HTTP.
+        let link_url = if url.starts_with("www.") {
+            format!("http://{url}")
+        } else {
+            url.to_string()
+        };
+        inlines.push(Inline::Link {
+            url: link_url,
+            autolink: true,
+            content: vec![Inline::Text(url.to_string())],
+        });
+        plain_text.push_str(rest);
+    }
+    if !plain_text.is_empty() {
+        inlines.push(Inline::Text(plain_text));
+    }
+}
+
+/// Length of the GFM extended autolink at the start of `word`, 0 if there is none.
+/// Trailing whitespace and punctuation is not considered part of the URL.
+fn bare_url_length(word: &str) -> usize {
+    let Some(prefix) = URL_PREFIXES.iter().find(|prefix| word.starts_with(*prefix)) else {
+        return 0;
+    };
+    let url = word.trim_end_matches(|ch: char| {
+        ch.is_whitespace() || matches!(ch, '.' | ',' | ':' | ';' | '!' | '?' | ')' | '"' | '\'')
+    });
+    if url.len() > prefix.len() {
+        url.len()
+    } else {
+        0
+    }
+}
+
+/// Strips a GitHub alert marker, e.g. `[!NOTE]`, from the first line of a blockquote,
+/// dropping its paragraph if the marker was all there was to it.
+fn take_alert_marker(blocks: &mut Vec<Block>) -> Option<AlertKind> {
+    let Some(Block {
+        kind: BlockKind::Paragraph(inlines),
+        ..
+    }) = blocks.first_mut()
+    else {
+        return None;
+    };
+    let first_line_length = inlines
+        .iter()
+        .position(|inline| *inline == Inline::SoftBreak)
+        .unwrap_or(inlines.len());
+    let alert_kind = AlertKind::parse(Inline::plain_text(&inlines[..first_line_length]).trim())?;
+    // Drop the soft break following the marker as well.
+    inlines.drain(..(first_line_length + 1).min(inlines.len()));
+    if inlines.is_empty() {
+        blocks.remove(0);
+    }
+    Some(alert_kind)
+}
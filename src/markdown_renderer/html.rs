@@ -0,0 +1,461 @@
+use super::{
+    alert::AlertKind,
+    code_block::{code_background, highlight_code_lines},
+    document::{
+        parse_document, Block, BlockKind, FootnoteNumbers, HeadingAnchors, Inline, ListItem,
+    },
+};
+use crate::config::theme::Theme;
+use pulldown_cmark::{Alignment, HeadingLevel};
+use ratatui::style::{Color, Modifier, Style};
+
+const HEADING_LEVELS: [HeadingLevel; 6] = [
+    HeadingLevel::H1,
+    HeadingLevel::H2,
+    HeadingLevel::H3,
+    HeadingLevel::H4,
+    HeadingLevel::H5,
+    HeadingLevel::H6,
+];
+const ALERT_KINDS: [AlertKind; 5] = [
+    AlertKind::Note,
+    AlertKind::Tip,
+    AlertKind::Important,
+    AlertKind::Warning,
+    AlertKind::Caution,
+];
+// Schemes of URLs that run script when followed, as stripped from links by GitHub.
+const UNSAFE_URL_SCHEMES: [&str; 3] = ["javascript", "vbscript", "data"];
+// Named colors are up to the terminal palette, so pages are
+// given a dark palette like that of most terminal themes.
+const PAGE_BACKGROUND: &str = "#1c1f26";
+const PAGE_FOREGROUND: &str = "#d8dee9";
+
+/// Renders markdown into a standalone HTML page, styled by the theme as in the terminal,
+/// and led by a table of contents linking to the anchors of its headings.
+pub fn render_html(markdown_str: &str, title: &str, theme: &Theme) -> String {
+    let mut html_writer = HtmlWriter::new(theme);
+    html_writer.write_blocks(&parse_document(markdown_str).blocks, false);
+    let body = html_writer.finish();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escaped(title),
+        stylesheet(theme)
+    )
+}
+
+struct HtmlWriter<'a> {
+    theme: &'a Theme,
+    html: String,
+    heading_anchors: HeadingAnchors,
+    // Level, title and anchor of each heading outside of footnotes.
+    table_of_contents: Vec<(HeadingLevel, String, String)>,
+    footnote_numbers: FootnoteNumbers,
+    // Written footnote definitions by footnote number, moved to the end of the page.
+    footnote_definitions: Vec<(usize, String)>,
+    in_footnote_definition: bool,
+}
+
+impl<'a> HtmlWriter<'a> {
+    fn new(theme: &'a Theme) -> Self {
+        HtmlWriter {
+            theme,
+            html: String::new(),
+            heading_anchors: HeadingAnchors::default(),
+            table_of_contents: Vec::new(),
+            footnote_numbers: FootnoteNumbers::default(),
+            footnote_definitions: Vec::new(),
+            in_footnote_definition: false,
+        }
+    }
+
+    /// Table of contents, followed by the document and a section of all footnote definitions.
+    fn finish(mut self) -> String {
+        let mut page = String::new();
+        if !self.table_of_contents.is_empty() {
+            page.push_str("<nav class=\"table-of-contents\">\n<ul>\n");
+            for (level, title, anchor) in &self.table_of_contents {
+                page.push_str(&format!(
+                    "<li class=\"toc-h{}\"><a href=\"#{}\">{}</a></li>\n",
+                    *level as usize,
+                    escaped(anchor),
+                    escaped(title)
+                ));
+            }
+            page.push_str("</ul>\n</nav>\n");
+        }
+        page.push_str("<main>\n");
+        page.push_str(&self.html);
+        if !self.footnote_definitions.is_empty() {
+            page.push_str("<section class=\"footnotes\">\n<hr>\n<ol>\n");
+            self.footnote_definitions
+                .sort_by_key(|(footnote_number, _)| *footnote_number);
+            for (footnote_number, definition) in &self.footnote_definitions {
+                page.push_str(&format!(
+                    "<li id=\"footnote-{footnote_number}\" value=\"{footnote_number}\">\n\
+                     {definition}</li>\n"
+                ));
+            }
+            page.push_str("</ol>\n</section>\n");
+        }
+        page.push_str("</main>\n");
+        page
+    }
+
+    /// Paragraphs of tight list items aren't wrapped in `<p>` elements.
+    fn write_blocks(&mut self, blocks: &[Block], tight: bool) {
+        for block in blocks {
+            self.write_block(block, tight);
+        }
+    }
+
+    fn write_block(&mut self, block: &Block, tight: bool) {
+        match &block.kind {
+            BlockKind::Paragraph(inlines) if tight => {
+                self.write_inlines(inlines);
+                self.html.push('\n');
+            }
+            BlockKind::Paragraph(inlines) => {
+                self.html.push_str("<p>");
+                self.write_inlines(inlines);
+                self.html.push_str("</p>\n");
+            }
+            BlockKind::Heading(level, inlines) => {
+                let title = Inline::plain_text(inlines);
+                let anchor = self.heading_anchors.unique_anchor(&title);
+                let level = *level as usize;
+                self.html.push_str(&format!(
+                    "<h{level} id=\"{0}\"><a class=\"anchor\" href=\"#{0}\">#</a> ",
+                    escaped(&anchor)
+                ));
+                self.write_inlines(inlines);
+                self.html.push_str(&format!("</h{level}>\n"));
+                if !self.in_footnote_definition {
+                    self.table_of_contents
+                        .push((HEADING_LEVELS[level - 1], title, anchor));
+                }
+            }
+            BlockKind::CodeBlock { language, code } => {
+                match language {
+                    Some(language) => self.html.push_str(&format!(
+                        "<pre><code class=\"language-{}\">",
+                        escaped(language)
+                    )),
+                    None => self.html.push_str("<pre><code>"),
+                }
+                let highlighted_lines = highlight_code_lines(code, language.as_deref(), self.theme);
+                for (line_index, highlighted_line) in highlighted_lines.into_iter().enumerate() {
+                    if line_index > 0 {
+                        self.html.push('\n');
+                    }
+                    for span in highlighted_line {
+                        // The background is that of the whole block.
+                        let declarations = css_declarations(Style {
+                            bg: None,
+                            ..span.style
+                        });
+                        self.html.push_str(&format!(
+                            "<span style=\"{declarations}\">{}</span>",
+                            escaped(&span.content)
+                        ));
+                    }
+                }
+                self.html.push_str("</code></pre>\n");
+            }
+            BlockKind::List {
+                start_number,
+                tight,
+                items,
+            } => {
+                let list_tag = match start_number {
+                    Some(1) => "<ol>".to_string(),
+                    Some(start_number) => format!("<ol start=\"{start_number}\">"),
+                    None => "<ul>".to_string(),
+                };
+                self.html.push_str(&list_tag);
+                self.html.push('\n');
+                for item in items {
+                    self.write_list_item(item, *tight);
+                }
+                let end_tag = if start_number.is_some() {
+                    "</ol>"
+                } else {
+                    "</ul>"
+                };
+                self.html.push_str(&format!("{end_tag}\n"));
+            }
+            BlockKind::BlockQuote { alert, blocks } => {
+                match alert {
+                    Some(alert) => self.html.push_str(&format!(
+                        "<blockquote class=\"alert alert-{}\">\n\
+                         <p class=\"alert-title\">{} {}</p>\n",
+                        alert_class(*alert),
+                        alert.icon(),
+                        alert.title()
+                    )),
+                    None => self.html.push_str("<blockquote>\n"),
+                }
+                self.write_blocks(blocks, false);
+                self.html.push_str("</blockquote>\n");
+            }
+            BlockKind::Table { alignments, rows } => {
+                self.html.push_str("<table>\n");
+                for (row_index, row) in rows.iter().enumerate() {
+                    let cell_tag = if row_index == 0 { "th" } else { "td" };
+                    match row_index {
+                        0 => self.html.push_str("<thead>\n"),
+                        1 => self.html.push_str("<tbody>\n"),
+                        _ => (),
+                    }
+                    self.html.push_str("<tr>");
+                    for (cell_index, cell) in row.iter().enumerate() {
+                        let alignment = match alignments.get(cell_index) {
+                            Some(Alignment::Left) => " style=\"text-align: left\"",
+                            Some(Alignment::Center) => " style=\"text-align: center\"",
+                            Some(Alignment::Right) => " style=\"text-align: right\"",
+                            Some(Alignment::None) | None => "",
+                        };
+                        self.html.push_str(&format!("<{cell_tag}{alignment}>"));
+                        self.write_inlines(cell);
+                        self.html.push_str(&format!("</{cell_tag}>"));
+                    }
+                    self.html.push_str("</tr>\n");
+                    if row_index == 0 {
+                        self.html.push_str("</thead>\n");
+                    }
+                }
+                if rows.len() > 1 {
+                    self.html.push_str("</tbody>\n");
+                }
+                self.html.push_str("</table>\n");
+            }
+            BlockKind::FootnoteDefinition { label, blocks } => {
+                // Written on its own, as it's moved to the end of the page.
+                let html = std::mem::take(&mut self.html);
+                self.in_footnote_definition = true;
+                self.write_blocks(blocks, false);
+                self.in_footnote_definition = false;
+                let definition = std::mem::replace(&mut self.html, html);
+                let footnote_number = self.footnote_numbers.number(label);
+                self.footnote_definitions
+                    .push((footnote_number, definition));
+            }
+            BlockKind::Rule => self.html.push_str("<hr>\n"),
+        }
+    }
+
+    fn write_list_item(&mut self, item: &ListItem, tight: bool) {
+        match item.task {
+            Some(checked) => {
+                let checked = if checked { " checked" } else { "" };
+                self.html.push_str(&format!(
+                    "<li class=\"task\"><input type=\"checkbox\" disabled{checked}> "
+                ));
+            }
+            None => self.html.push_str("<li>"),
+        }
+        self.write_blocks(&item.blocks, tight);
+        self.html.push_str("</li>\n");
+    }
+
+    fn write_inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => self.html.push_str(&escaped(text)),
+                Inline::Code(code) => {
+                    self.html
+                        .push_str(&format!("<code>{}</code>", escaped(code)));
+                }
+                Inline::Emphasis(content) => self.write_enclosed("em", content),
+                Inline::Strong(content) => self.write_enclosed("strong", content),
+                Inline::Strikethrough(content) => self.write_enclosed("del", content),
+                Inline::Link { url, content, .. } => {
+                    // Pages are meant to be shared, so links that would run script are kept
+                    // without their destination.
+                    if is_unsafe_url(url) {
+                        self.html.push_str("<a>");
+                    } else {
+                        self.html
+                            .push_str(&format!("<a href=\"{}\">", escaped(url)));
+                    }
+                    self.write_inlines(content);
+                    self.html.push_str("</a>");
+                }
+                // Sources that would run script are dropped like link destinations.
+                Inline::Image { url, alt } => {
+                    let alt = escaped(&Inline::plain_text(alt));
+                    if is_unsafe_url(url) {
+                        self.html.push_str(&format!("<img alt=\"{alt}\">"));
+                    } else {
+                        self.html
+                            .push_str(&format!("<img src=\"{}\" alt=\"{alt}\">", escaped(url)));
+                    }
+                }
+                Inline::FootnoteReference(label) => {
+                    let footnote_number = self.footnote_numbers.number(label);
+                    self.html.push_str(&format!(
+                        "<sup class=\"footnote-reference\">\
+                         <a href=\"#footnote-{footnote_number}\">{footnote_number}</a></sup>"
+                    ));
+                }
+                Inline::SoftBreak => self.html.push('\n'),
+                Inline::HardBreak => self.html.push_str("<br>\n"),
+            }
+        }
+    }
+
+    fn write_enclosed(&mut self, tag: &str, content: &[Inline]) {
+        self.html.push_str(&format!("<{tag}>"));
+        self.write_inlines(content);
+        self.html.push_str(&format!("</{tag}>"));
+    }
+}
+
+/// Styles the elements as their terminal counterparts are styled.
+fn stylesheet(theme: &Theme) -> String {
+    let code_background = css_color(code_background(theme)).unwrap_or_default();
+    let border_color = css_color(theme.rule.fg.unwrap_or(Color::DarkGray)).unwrap_or_default();
+    let blockquote_color =
+        css_color(theme.blockquote.fg.unwrap_or(Color::DarkGray)).unwrap_or_default();
+    let table_border_color =
+        css_color(theme.table_border.fg.unwrap_or(Color::DarkGray)).unwrap_or_default();
+    let code_block_border_color =
+        css_color(theme.code_block_border.fg.unwrap_or(Color::DarkGray)).unwrap_or_default();
+    let mut stylesheet = format!(
+        "body {{ max-width: 50em; margin: 0 auto; padding: 2em; \
+         background-color: {PAGE_BACKGROUND}; color: {PAGE_FOREGROUND}; \
+         font-family: sans-serif; line-height: 1.5; }}\n\
+         nav.table-of-contents {{ border-bottom: 1px solid {border_color}; margin-bottom: 2em; }}\n\
+         nav.table-of-contents ul {{ list-style: none; padding-left: 0; }}\n\
+         nav.table-of-contents a {{ color: inherit; text-decoration: none; }}\n\
+         a.anchor {{ color: {border_color}; text-decoration: none; }}\n\
+         code {{ background-color: {code_background}; padding: 0.1em 0.3em; \
+         border-radius: 3px; }}\n\
+         pre {{ background-color: {code_background}; padding: 1em; overflow-x: auto; \
+         border: 1px solid {code_block_border_color}; border-radius: 6px; }}\n\
+         pre code {{ padding: 0; }}\n\
+         blockquote {{ margin: 0; padding: 0 1em; \
+         border-left: 0.25em solid {blockquote_color}; }}\n\
+         .alert-title {{ font-weight: bold; }}\n\
+         li.task {{ list-style: none; }}\n\
+         table {{ border-collapse: collapse; }}\n\
+         th, td {{ border: 1px solid {table_border_color}; padding: 0.3em 0.6em; }}\n\
+         hr {{ border: none; border-top: 1px solid {border_color}; }}\n"
+    );
+    for level in HEADING_LEVELS {
+        let level_number = level as usize;
+        stylesheet.push_str(&format!(
+            "h{level_number} {{ {} }}\n\
+             .toc-h{level_number} {{ padding-left: {}em; }}\n",
+            css_declarations(theme.heading(level)),
+            level_number - 1
+        ));
+    }
+    for alert in ALERT_KINDS {
+        let alert_style = alert.style(theme);
+        let alert_color = css_color(alert_style.fg.unwrap_or(Color::DarkGray)).unwrap_or_default();
+        stylesheet.push_str(&format!(
+            ".alert-{0} {{ border-left-color: {alert_color}; }}\n\
+             .alert-{0} .alert-title {{ {1} }}\n",
+            alert_class(alert),
+            css_declarations(alert_style)
+        ));
+    }
+    stylesheet.push_str(&format!(
+        "a {{ {} }}\nli::marker {{ {} }}\n.footnote-reference, .footnotes li::marker {{ {} }}\n\
+         :not(pre) > code {{ {} }}\n",
+        css_declarations(theme.link),
+        css_declarations(theme.list_marker),
+        css_declarations(theme.footnote_marker),
+        css_declarations(theme.inline_code)
+    ));
+    stylesheet
+}
+
+/// Whether the URL has an unsafe scheme, ignoring case and the whitespace
+/// and control characters that browsers skip over within it.
+fn is_unsafe_url(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return false;
+    };
+    let scheme: String = scheme
+        .chars()
+        .filter(|ch| !ch.is_whitespace() && !ch.is_control())
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect();
+    UNSAFE_URL_SCHEMES.contains(&scheme.as_str())
+}
+
+fn alert_class(alert: AlertKind) -> String {
+    alert.title().to_lowercase()
+}
+
+fn css_declarations(style: Style) -> String {
+    let mut declarations = Vec::new();
+    if let Some(color) = style.fg.and_then(css_color) {
+        declarations.push(format!("color: {color}"));
+    }
+    if let Some(background_color) = style.bg.and_then(css_color) {
+        declarations.push(format!("background-color: {background_color}"));
+    }
+    if style.add_modifier.contains(Modifier::BOLD) {
+        declarations.push("font-weight: bold".to_string());
+    }
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        declarations.push("font-style: italic".to_string());
+    }
+    let text_decorations: Vec<&str> = [
+        (Modifier::UNDERLINED, "underline"),
+        (Modifier::CROSSED_OUT, "line-through"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+    .map(|(_, text_decoration)| text_decoration)
+    .collect();
+    if !text_decorations.is_empty() {
+        declarations.push(format!("text-decoration: {}", text_decorations.join(" ")));
+    }
+    declarations.join("; ")
+}
+
+/// Palette of named colors, indexed colors being left to the page foreground.
+fn css_color(color: Color) -> Option<String> {
+    let css_color = match color {
+        Color::Reset | Color::Indexed(_) => return None,
+        Color::Black => "#000000",
+        Color::Red => "#e06c75",
+        Color::Green => "#98c379",
+        Color::Yellow => "#e5c07b",
+        Color::Blue => "#61afef",
+        Color::Magenta => "#c678dd",
+        Color::Cyan => "#56b6c2",
+        Color::Gray => "#abb2bf",
+        Color::DarkGray => "#5c6370",
+        Color::LightRed => "#ff7b86",
+        Color::LightGreen => "#b5e890",
+        Color::LightYellow => "#ffd68a",
+        Color::LightBlue => "#82c4ff",
+        Color::LightMagenta => "#de95f0",
+        Color::LightCyan => "#7fd8e3",
+        Color::White => "#ffffff",
+        Color::Rgb(r, g, b) => return Some(format!("#{r:02x}{g:02x}{b:02x}")),
+    };
+    Some(css_color.to_string())
+}
+
+fn escaped(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
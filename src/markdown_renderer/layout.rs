@@ -0,0 +1,625 @@
+use super::{
+    code_block::{code_background, render_code_block},
+    document::{Block, BlockKind, Document, FootnoteNumbers, HeadingAnchors, Inline, ListItem},
+    table::render_table,
+    wrap::wrap_spans,
+};
+use crate::config::theme::Theme;
+use pulldown_cmark::HeadingLevel;
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+};
+use std::ops::Range;
+
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+// Stands in for hard breaks among inline spans, as text spans never contain newlines.
+const HARD_BREAK: &str = "\n";
+
+/// Document laid out into styled lines of a given width.
+pub struct DocumentLayout {
+    pub lines: Vec<Spans<'static>>,
+    /// First markdown source line of the block each rendered line belongs to.
+    pub source_lines: Vec<usize>,
+    /// Headings in document order, making up its table of contents.
+    pub headings: Vec<HeadingPosition>,
+    /// Links in document order, except for those within tables.
+    pub links: Vec<LinkPosition>,
+}
+
+pub struct HeadingPosition {
+    pub level: HeadingLevel,
+    pub title: String,
+    /// Unique anchor of the heading, as linked to by `#anchor` fragments.
+    pub anchor: String,
+    /// Index of the first rendered line of the heading.
+    pub line_index: usize,
+}
+
+pub struct LinkPosition {
+    pub url: String,
+    /// Line indices and byte ranges within the line text of the link text,
+    /// one for each line the link text has been wrapped onto.
+    pub segments: Vec<(usize, Range<usize>)>,
+}
+
+/// Position in a document that survives changes to its content and width,
+/// as opposed to a plain line index.
+pub struct ScrollAnchor {
+    // Title of the heading at or above the top of the view,
+    // along with how many headings before it share that title.
+    heading: Option<(String, usize)>,
+    // Source line of the block at the top of the view, also relative to the heading.
+    source_line: usize,
+    source_lines_below_heading: usize,
+    // Rendered lines from the start of that block to the top of the view.
+    line_offset: usize,
+}
+
+impl DocumentLayout {
+    /// Heading of the section the line belongs to.
+    pub fn heading_index_at(&self, line_index: usize) -> Option<usize> {
+        self.headings
+            .iter()
+            .rposition(|heading| heading.line_index <= line_index)
+    }
+
+    pub fn scroll_anchor(&self, scroll_offset: usize) -> Option<ScrollAnchor> {
+        let source_line = *self.source_lines.get(scroll_offset)?;
+        let mut source_lines_below_heading = 0;
+        let block_start_index = self
+            .source_lines
+            .iter()
+            .position(|line_source_line| *line_source_line == source_line)
+            .unwrap_or(scroll_offset);
+        let heading = self.heading_index_at(scroll_offset).map(|heading_index| {
+            let heading = &self.headings[heading_index];
+            // Footnote definitions are drawn last, despite lying above the heading in source.
+            source_lines_below_heading =
+                source_line.saturating_sub(self.source_lines[heading.line_index]);
+            let same_title_count = self.headings[..heading_index]
+                .iter()
+                .filter(|other_heading| other_heading.title == heading.title)
+                .count();
+            (heading.title.clone(), same_title_count)
+        });
+        Some(ScrollAnchor {
+            heading,
+            source_line,
+            source_lines_below_heading,
+            line_offset: scroll_offset - block_start_index,
+        })
+    }
+
+    /// Scroll offset of the anchor within this layout, falling back to the absolute
+    /// source line should the anchoring heading be gone. Always within the document.
+    pub fn anchored_scroll_offset(&self, scroll_anchor: &ScrollAnchor) -> usize {
+        let heading_source_line =
+            scroll_anchor
+                .heading
+                .as_ref()
+                .and_then(|(title, same_title_count)| {
+                    self.headings
+                        .iter()
+                        .filter(|heading| heading.title == *title)
+                        .nth(*same_title_count)
+                        .map(|heading| self.source_lines[heading.line_index])
+                });
+        let source_line = match heading_source_line {
+            Some(heading_source_line) => {
+                heading_source_line + scroll_anchor.source_lines_below_heading
+            }
+            None => scroll_anchor.source_line,
+        };
+        let scroll_offset = self
+            .source_lines
+            .iter()
+            .position(|line_source_line| *line_source_line >= source_line)
+            .map_or(self.lines.len(), |block_start_index| {
+                block_start_index + scroll_anchor.line_offset
+            });
+        scroll_offset.min(self.lines.len().saturating_sub(1))
+    }
+}
+
+impl LinkPosition {
+    pub fn line_index(&self) -> usize {
+        self.segments[0].0
+    }
+}
+
+pub fn layout_document(document: &Document, width: u16, theme: &Theme) -> DocumentLayout {
+    let mut layout = Layout::new(width, theme);
+    layout.layout_blocks(&document.blocks, false);
+    layout.finish()
+}
+
+struct Layout<'a> {
+    width: u16,
+    theme: &'a Theme,
+    lines: Vec<Spans<'static>>,
+    source_lines: Vec<usize>,
+    headings: Vec<HeadingPosition>,
+    heading_anchors: HeadingAnchors,
+    links: Vec<LinkPosition>,
+    // Char ranges of the links among the inline spans yet to be wrapped.
+    pending_links: Vec<(String, Range<usize>)>,
+    // Source line of the block currently being laid out.
+    current_source_line: usize,
+    // Blank lines may carry the indentation of their containers,
+    // such as blockquote gutters, so they are tracked separately.
+    last_line_blank: bool,
+    indent_stack: Vec<Indent>,
+    list_depth: usize,
+    footnote_numbers: FootnoteNumbers,
+    // Laid out footnote definitions by footnote number, moved to the end of the document.
+    footnote_definitions: Vec<(usize, DocumentLayout)>,
+    // Link destinations in order of first appearance, which gives their reference number.
+    link_urls: Vec<String>,
+}
+
+/// Indentation added by a container block, such as a list item, to each of its lines.
+/// The marker takes the place of the continuation on the container's first line.
+struct Indent {
+    marker: Option<Span<'static>>,
+    continuation: Span<'static>,
+}
+
+impl Indent {
+    /// Indents lines by the width of the marker.
+    fn marker(marker: Span<'static>) -> Self {
+        Indent {
+            continuation: Span::raw(" ".repeat(marker.width())),
+            marker: Some(marker),
+        }
+    }
+
+    /// Indents every line with a gutter, such as that of blockquotes.
+    fn gutter(gutter: Span<'static>) -> Self {
+        Indent {
+            marker: None,
+            continuation: gutter,
+        }
+    }
+}
+
+impl<'a> Layout<'a> {
+    fn new(width: u16, theme: &'a Theme) -> Self {
+        Layout {
+            width,
+            theme,
+            lines: Vec::new(),
+            source_lines: Vec::new(),
+            headings: Vec::new(),
+            heading_anchors: HeadingAnchors::default(),
+            links: Vec::new(),
+            pending_links: Vec::new(),
+            current_source_line: 0,
+            last_line_blank: false,
+            indent_stack: Vec::new(),
+            list_depth: 0,
+            footnote_numbers: FootnoteNumbers::default(),
+            footnote_definitions: Vec::new(),
+            link_urls: Vec::new(),
+        }
+    }
+
+    /// Laid out document lines, followed by a section of all
+    /// footnote definitions and numbered link references.
+    fn finish(mut self) -> DocumentLayout {
+        if !self.footnote_definitions.is_empty() || !self.link_urls.is_empty() {
+            self.push_blank_line();
+            self.push_line(vec![Span::styled(
+                "─".repeat(self.width as usize),
+                self.theme.rule,
+            )]);
+            self.footnote_definitions
+                .sort_by_key(|(footnote_number, _)| *footnote_number);
+            for (_, definition) in std::mem::take(&mut self.footnote_definitions) {
+                let line_offset = self.lines.len();
+                self.links
+                    .extend(definition.links.into_iter().map(|mut link| {
+                        for (line_index, _) in &mut link.segments {
+                            *line_index += line_offset;
+                        }
+                        link
+                    }));
+                self.lines.extend(definition.lines);
+                self.source_lines.extend(definition.source_lines);
+            }
+            for (link_index, link_url) in
+                std::mem::take(&mut self.link_urls).into_iter().enumerate()
+            {
+                self.push_wrapped(vec![
+                    Span::styled(format!("[{}] ", link_index + 1), self.theme.link_reference),
+                    Span::styled(link_url, self.theme.link),
+                ]);
+            }
+        }
+        DocumentLayout {
+            lines: self.lines,
+            source_lines: self.source_lines,
+            headings: self.headings,
+            links: self.links,
+        }
+    }
+
+    /// Blocks of tight list items aren't separated by blank lines.
+    fn layout_blocks(&mut self, blocks: &[Block], tight: bool) {
+        for block in blocks {
+            self.layout_block(block, tight);
+        }
+    }
+
+    fn layout_block(&mut self, block: &Block, tight: bool) {
+        self.current_source_line = block.source_lines.start;
+        match &block.kind {
+            BlockKind::Paragraph(inlines) => {
+                if !tight {
+                    self.push_blank_line();
+                }
+                let mut spans = Vec::new();
+                self.push_inline_spans(inlines, Style::default(), &mut spans);
+                self.push_wrapped(spans);
+            }
+            BlockKind::Heading(level, inlines) => {
+                self.push_blank_line();
+                let title = Inline::plain_text(inlines);
+                let anchor = self.heading_anchors.unique_anchor(&title);
+                self.headings.push(HeadingPosition {
+                    level: *level,
+                    title,
+                    anchor,
+                    line_index: self.lines.len(),
+                });
+                let heading_style = self.theme.heading(*level);
+                let mut spans = vec![Span::styled(
+                    format!("{} ", "#".repeat(*level as usize)),
+                    heading_style,
+                )];
+                self.push_inline_spans(inlines, heading_style, &mut spans);
+                self.push_wrapped(spans);
+                self.push_blank_line();
+            }
+            BlockKind::CodeBlock { language, code } => {
+                self.push_blank_line();
+                let code_block_lines =
+                    render_code_block(code, language.as_deref(), self.block_width(), self.theme);
+                self.push_block_lines(code_block_lines);
+            }
+            BlockKind::List {
+                start_number,
+                tight,
+                items,
+            } => {
+                if self.list_depth == 0 {
+                    self.push_blank_line();
+                }
+                self.list_depth += 1;
+                for (item_index, item) in items.iter().enumerate() {
+                    let item_number =
+                        start_number.map(|start_number| start_number + item_index as u64);
+                    self.layout_list_item(item, item_number, *tight);
+                }
+                self.list_depth -= 1;
+                if self.list_depth == 0 {
+                    self.push_blank_line();
+                }
+            }
+            BlockKind::BlockQuote { alert, blocks } => {
+                self.push_blank_line();
+                let gutter_style =
+                    alert.map_or(self.theme.blockquote, |alert| alert.style(self.theme));
+                self.indent_stack
+                    .push(Indent::gutter(Span::styled("│ ", gutter_style)));
+                if let Some(alert) = alert {
+                    let mut title_line = self.take_prefix();
+                    title_line.push(Span::styled(
+                        format!("{} {}", alert.icon(), alert.title()),
+                        gutter_style.add_modifier(Modifier::BOLD),
+                    ));
+                    self.push_line(title_line);
+                }
+                // The title of an alert sits right above its first paragraph.
+                if let Some((first_block, blocks)) = blocks.split_first() {
+                    self.layout_block(first_block, alert.is_some());
+                    self.layout_blocks(blocks, false);
+                }
+                // Let the blank line after the blockquote be drawn without its gutter.
+                if self.last_line_blank {
+                    self.lines.pop();
+                    self.source_lines.pop();
+                    self.last_line_blank = false;
+                }
+                self.indent_stack.pop();
+            }
+            BlockKind::Table { alignments, rows } => {
+                self.push_blank_line();
+                let header_style = Style::default().add_modifier(Modifier::BOLD);
+                let rows = rows
+                    .iter()
+                    .enumerate()
+                    .map(|(row_index, row)| {
+                        let cell_style = if row_index == 0 {
+                            header_style
+                        } else {
+                            Style::default()
+                        };
+                        row.iter()
+                            .map(|cell| {
+                                let mut cell_spans = Vec::new();
+                                self.push_inline_spans(cell, cell_style, &mut cell_spans);
+                                cell_spans
+                            })
+                            .collect()
+                    })
+                    .collect();
+                // Table cells are laid out on their own, without keeping track of their links.
+                self.pending_links.clear();
+                let table_lines = render_table(
+                    alignments,
+                    rows,
+                    self.block_width(),
+                    self.theme.table_border,
+                );
+                self.push_block_lines(table_lines);
+            }
+            BlockKind::FootnoteDefinition { label, blocks } => {
+                let marker = format!("{} ", superscript(self.footnote_numbers.number(label)));
+                // Lay out the definition on its own, as it's moved to the end of the document.
+                let lines = std::mem::take(&mut self.lines);
+                let source_lines = std::mem::take(&mut self.source_lines);
+                let headings = std::mem::take(&mut self.headings);
+                let links = std::mem::take(&mut self.links);
+                let last_line_blank = std::mem::replace(&mut self.last_line_blank, false);
+                self.indent_stack.push(Indent::marker(Span::styled(
+                    marker,
+                    self.theme.footnote_marker,
+                )));
+                self.layout_blocks(blocks, false);
+                self.indent_stack.pop();
+                let definition = DocumentLayout {
+                    lines: std::mem::replace(&mut self.lines, lines),
+                    source_lines: std::mem::replace(&mut self.source_lines, source_lines),
+                    headings: std::mem::replace(&mut self.headings, headings),
+                    links: std::mem::replace(&mut self.links, links),
+                };
+                self.last_line_blank = last_line_blank;
+                let footnote_number = self.footnote_numbers.number(label);
+                self.footnote_definitions
+                    .push((footnote_number, definition));
+            }
+            BlockKind::Rule => {
+                self.push_blank_line();
+                let mut rule_line = self.take_prefix();
+                rule_line.push(Span::styled(
+                    "─".repeat(self.block_width() as usize),
+                    self.theme.rule,
+                ));
+                self.push_line(rule_line);
+                self.push_blank_line();
+            }
+        }
+    }
+
+    /// Numbered items keep their number in front of any task checkbox,
+    /// whereas the checkbox replaces the bullet of unnumbered items.
+    fn layout_list_item(&mut self, item: &ListItem, item_number: Option<u64>, tight: bool) {
+        let mut marker = match item_number {
+            Some(item_number) => format!("{item_number}. "),
+            None => format!("{} ", BULLETS[(self.list_depth - 1) % BULLETS.len()]),
+        };
+        if let Some(checked) = item.task {
+            let checkbox = if checked { "☑ " } else { "☐ " };
+            match item_number {
+                Some(_) => marker.push_str(checkbox),
+                None => marker = checkbox.to_string(),
+            }
+        }
+        self.indent_stack
+            .push(Indent::marker(Span::styled(marker, self.theme.list_marker)));
+        self.layout_blocks(&item.blocks, tight);
+        // Empty list items still show their marker.
+        if self
+            .indent_stack
+            .last()
+            .is_some_and(|indent| indent.marker.is_some())
+        {
+            let prefix = self.take_prefix();
+            self.push_line(prefix);
+        }
+        self.indent_stack.pop();
+    }
+
+    /// Appends the spans of inlines, keeping track of where links start and end among them.
+    fn push_inline_spans(
+        &mut self,
+        inlines: &[Inline],
+        style: Style,
+        spans: &mut Vec<Span<'static>>,
+    ) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => spans.push(Span::styled(text.clone(), style)),
+                Inline::Code(code) => spans.push(Span::styled(
+                    code.clone(),
+                    style
+                        .bg(code_background(self.theme))
+                        .patch(self.theme.inline_code),
+                )),
+                Inline::Emphasis(content) => {
+                    self.push_inline_spans(content, style.add_modifier(Modifier::ITALIC), spans)
+                }
+                Inline::Strong(content) => {
+                    self.push_inline_spans(content, style.add_modifier(Modifier::BOLD), spans)
+                }
+                Inline::Strikethrough(content) => self.push_inline_spans(
+                    content,
+                    style.add_modifier(Modifier::CROSSED_OUT),
+                    spans,
+                ),
+                Inline::Link {
+                    url,
+                    autolink,
+                    content,
+                } => {
+                    let link_start = char_count(spans);
+                    self.push_inline_spans(content, style.patch(self.theme.link), spans);
+                    self.pending_links
+                        .push((url.clone(), link_start..char_count(spans)));
+                    // Autolinks already show their destination.
+                    if !autolink {
+                        let link_number = self.link_number(url);
+                        spans.push(Span::styled(
+                            format!("[{link_number}]"),
+                            self.theme.link_reference,
+                        ));
+                    }
+                }
+                // Images are shown by their alt text.
+                Inline::Image { alt, .. } => self.push_inline_spans(alt, style, spans),
+                Inline::FootnoteReference(label) => {
+                    let footnote_number = self.footnote_numbers.number(label);
+                    spans.push(Span::styled(
+                        superscript(footnote_number),
+                        self.theme.footnote_marker,
+                    ));
+                }
+                // Soft breaks only separate words when joined into a single wrapped line.
+                Inline::SoftBreak => spans.push(Span::styled(" ", style)),
+                Inline::HardBreak => spans.push(Span::raw(HARD_BREAK)),
+            }
+        }
+    }
+
+    fn link_number(&mut self, url: &str) -> usize {
+        match self.link_urls.iter().position(|link_url| link_url == url) {
+            Some(index) => index + 1,
+            None => {
+                self.link_urls.push(url.to_string());
+                self.link_urls.len()
+            }
+        }
+    }
+
+    /// Wraps inline spans into indented lines, breaking lines at hard breaks.
+    /// Pending links are positioned on the lines their text was wrapped onto.
+    fn push_wrapped(&mut self, spans: Vec<Span<'static>>) {
+        let input_chars: Vec<char> = spans.iter().flat_map(|span| span.content.chars()).collect();
+        // Line index, byte offset within the line text and byte length of each input char.
+        let mut char_positions: Vec<Option<(usize, usize, usize)>> = vec![None; input_chars.len()];
+        let mut input_index = 0;
+        for hard_line in spans.split(|span| span.content == HARD_BREAK) {
+            for wrapped_line in wrap_spans(hard_line.to_vec(), self.block_width() as usize) {
+                let mut line = self.take_prefix();
+                let mut byte_offset: usize = line.iter().map(|span| span.content.len()).sum();
+                for ch in wrapped_line.iter().flat_map(|span| span.content.chars()) {
+                    // Skipping over hard breaks and whitespace dropped at the start of lines.
+                    while input_chars
+                        .get(input_index)
+                        .is_some_and(|&input_char| input_char != ch)
+                    {
+                        input_index += 1;
+                    }
+                    if let Some(char_position) = char_positions.get_mut(input_index) {
+                        *char_position = Some((self.lines.len(), byte_offset, ch.len_utf8()));
+                    }
+                    input_index += 1;
+                    byte_offset += ch.len_utf8();
+                }
+                line.extend(wrapped_line);
+                self.push_line(line);
+            }
+        }
+        for (url, char_range) in std::mem::take(&mut self.pending_links) {
+            let mut segments: Vec<(usize, Range<usize>)> = Vec::new();
+            for (line_index, byte_offset, byte_length) in char_range
+                .filter_map(|char_index| char_positions.get(char_index).copied().flatten())
+            {
+                match segments.last_mut() {
+                    Some((last_line_index, byte_range)) if *last_line_index == line_index => {
+                        byte_range.end = byte_offset + byte_length
+                    }
+                    _ => segments.push((line_index, byte_offset..byte_offset + byte_length)),
+                }
+            }
+            if !segments.is_empty() {
+                self.links.push(LinkPosition { url, segments });
+            }
+        }
+    }
+
+    /// Indents pre-rendered block lines, such as those of code blocks and tables.
+    fn push_block_lines(&mut self, block_lines: Vec<Spans<'static>>) {
+        for block_line in block_lines {
+            let mut line = self.take_prefix();
+            line.extend(block_line.0);
+            self.push_line(line);
+        }
+        self.push_blank_line();
+    }
+
+    /// Width available to blocks after the indentation of their containers.
+    fn block_width(&self) -> u16 {
+        (self.width as usize).saturating_sub(self.indent_width()) as u16
+    }
+
+    fn indent_width(&self) -> usize {
+        self.indent_stack
+            .iter()
+            .map(|indent| indent.continuation.width())
+            .sum()
+    }
+
+    /// Indentation for the next line, consuming any pending container markers.
+    fn take_prefix(&mut self) -> Vec<Span<'static>> {
+        self.indent_stack
+            .iter_mut()
+            .map(|indent| {
+                indent
+                    .marker
+                    .take()
+                    .unwrap_or_else(|| indent.continuation.clone())
+            })
+            .collect()
+    }
+
+    fn push_line(&mut self, line: Vec<Span<'static>>) {
+        self.lines.push(Spans::from(line));
+        self.source_lines.push(self.current_source_line);
+        self.last_line_blank = false;
+    }
+
+    /// Separates blocks by a single blank line, never leading the document with one.
+    /// Blank lines keep the gutters of their containers, but not their markers.
+    fn push_blank_line(&mut self) {
+        if self.lines.is_empty() || self.last_line_blank {
+            return;
+        }
+        let blank_line: Vec<Span<'static>> = self
+            .indent_stack
+            .iter()
+            .map(|indent| {
+                let gutter = indent.continuation.content.trim_end().to_string();
+                Span::styled(gutter, indent.continuation.style)
+            })
+            .collect();
+        self.lines.push(Spans::from(blank_line));
+        self.source_lines.push(self.current_source_line);
+        self.last_line_blank = true;
+    }
+}
+
+fn char_count(spans: &[Span]) -> usize {
+    spans.iter().map(|span| span.content.chars().count()).sum()
+}
+
+fn superscript(number: usize) -> String {
+    number
+        .to_string()
+        .chars()
+        .filter_map(|digit| digit.to_digit(10))
+        .map(|digit| SUPERSCRIPT_DIGITS[digit as usize])
+        .collect()
+}
@@ -0,0 +1,209 @@
+use super::wrap::wrap_spans;
+use pulldown_cmark::Alignment;
+use ratatui::{
+    style::Style,
+    text::{Span, Spans},
+};
+use unicode_width::UnicodeWidthChar;
+
+// Columns narrower than this are truncated rather than wrapped,
+// as wrapping into a handful of characters per line is hardly readable.
+const MIN_WRAP_WIDTH: usize = 6;
+const ELLIPSIS: char = '…';
+
+/// Renders a GFM table as a box drawn grid no wider than `width`, or as unbordered rows
+/// if too narrow to leave a column of space per table column. The first row is the header.
+pub fn render_table(
+    alignments: &[Alignment],
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    width: u16,
+    border_style: Style,
+) -> Vec<Spans<'static>> {
+    let column_count = alignments.len();
+    if column_count == 0 {
+        return Vec::new();
+    }
+    // Each column is padded by a space on both sides and followed by a border,
+    // with an additional border at the start of the row.
+    if (width as usize) < 4 * column_count + 1 {
+        return render_unbordered_rows(rows, width as usize, border_style);
+    }
+    let column_widths = fit_column_widths(&rows, column_count, width as usize);
+
+    let mut lines = vec![horizontal_border(
+        &column_widths,
+        ('┌', '┬', '┐'),
+        border_style,
+    )];
+    for (row_index, mut row) in rows.into_iter().enumerate() {
+        if row_index == 1 {
+            lines.push(horizontal_border(
+                &column_widths,
+                ('├', '┼', '┤'),
+                border_style,
+            ));
+        }
+        row.resize_with(column_count, Vec::new);
+        let cell_lines: Vec<Vec<Vec<Span<'static>>>> = row
+            .into_iter()
+            .zip(&column_widths)
+            .map(|(cell, column_width)| fit_cell(cell, *column_width))
+            .collect();
+        let row_height = cell_lines.iter().map(Vec::len).max().unwrap_or(1);
+        for line_index in 0..row_height {
+            let mut line = vec![Span::styled("│", border_style)];
+            for (column_index, cell) in cell_lines.iter().enumerate() {
+                let cell_line = cell.get(line_index).cloned().unwrap_or_default();
+                line.push(Span::raw(" "));
+                line.extend(align(
+                    cell_line,
+                    column_widths[column_index],
+                    alignments[column_index],
+                ));
+                line.push(Span::raw(" "));
+                line.push(Span::styled("│", border_style));
+            }
+            lines.push(Spans::from(line));
+        }
+    }
+    lines.push(horizontal_border(
+        &column_widths,
+        ('└', '┴', '┘'),
+        border_style,
+    ));
+    lines
+}
+
+/// Lists the cells of each row on lines of their own, rows being separated by a rule.
+fn render_unbordered_rows(
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    width: usize,
+    border_style: Style,
+) -> Vec<Spans<'static>> {
+    let mut lines = Vec::new();
+    for (row_index, row) in rows.into_iter().enumerate() {
+        if row_index != 0 {
+            lines.push(Spans::from(Span::styled("─".repeat(width), border_style)));
+        }
+        for cell in row {
+            lines.extend(wrap_spans(cell, width).into_iter().map(Spans::from));
+        }
+    }
+    lines
+}
+
+/// Natural column widths if they fit, otherwise the available width is shared
+/// so that narrow columns keep their natural width and wide columns split the rest.
+fn fit_column_widths(
+    rows: &[Vec<Vec<Span<'static>>>],
+    column_count: usize,
+    width: usize,
+) -> Vec<usize> {
+    let mut natural_widths = vec![1; column_count];
+    for row in rows {
+        for (column_index, cell) in row.iter().enumerate().take(column_count) {
+            let cell_width = cell.iter().map(Span::width).sum();
+            natural_widths[column_index] = natural_widths[column_index].max(cell_width);
+        }
+    }
+    let available_width = width.saturating_sub(3 * column_count + 1);
+    if natural_widths.iter().sum::<usize>() <= available_width {
+        return natural_widths;
+    }
+
+    let mut column_widths = vec![0; column_count];
+    let mut unfitted_columns: Vec<usize> = (0..column_count).collect();
+    let mut remaining_width = available_width;
+    loop {
+        let fair_share = (remaining_width / unfitted_columns.len().max(1)).max(1);
+        let (narrow_columns, wide_columns): (Vec<usize>, Vec<usize>) = unfitted_columns
+            .iter()
+            .partition(|column_index| natural_widths[**column_index] <= fair_share);
+        if narrow_columns.is_empty() {
+            for column_index in &wide_columns {
+                column_widths[*column_index] = fair_share;
+            }
+            // Hand out what integer division left over, left to right.
+            let leftover = remaining_width.saturating_sub(fair_share * wide_columns.len());
+            for column_index in wide_columns.iter().take(leftover) {
+                column_widths[*column_index] += 1;
+            }
+            return column_widths;
+        }
+        for column_index in narrow_columns {
+            column_widths[column_index] = natural_widths[column_index];
+            remaining_width = remaining_width.saturating_sub(natural_widths[column_index]);
+        }
+        if wide_columns.is_empty() {
+            return column_widths;
+        }
+        unfitted_columns = wide_columns;
+    }
+}
+
+fn horizontal_border(
+    column_widths: &[usize],
+    (left, middle, right): (char, char, char),
+    border_style: Style,
+) -> Spans<'static> {
+    let mut border = String::from(left);
+    for (column_index, column_width) in column_widths.iter().enumerate() {
+        if column_index != 0 {
+            border.push(middle);
+        }
+        border.push_str(&"─".repeat(column_width + 2));
+    }
+    border.push(right);
+    Spans::from(Span::styled(border, border_style))
+}
+
+/// Wraps the cell contents to the column width, or truncates them
+/// with an ellipsis if the column is too narrow to wrap into.
+fn fit_cell(cell: Vec<Span<'static>>, column_width: usize) -> Vec<Vec<Span<'static>>> {
+    if column_width >= MIN_WRAP_WIDTH {
+        return wrap_spans(cell, column_width);
+    }
+    let cell_width: usize = cell.iter().map(Span::width).sum();
+    if cell_width <= column_width {
+        return vec![cell];
+    }
+    let mut truncated_line = Vec::new();
+    let mut line_width = 0;
+    'spans: for span in cell {
+        let mut content = String::new();
+        for ch in span.content.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            // Reserve a column for the ellipsis.
+            if line_width + ch_width + 1 > column_width {
+                truncated_line.push(Span::styled(content, span.style));
+                break 'spans;
+            }
+            content.push(ch);
+            line_width += ch_width;
+        }
+        truncated_line.push(Span::styled(content, span.style));
+    }
+    let ellipsis_style = truncated_line
+        .last()
+        .map(|span| span.style)
+        .unwrap_or_default();
+    truncated_line.push(Span::styled(ELLIPSIS.to_string(), ellipsis_style));
+    vec![truncated_line]
+}
+
+fn align(
+    mut line: Vec<Span<'static>>,
+    column_width: usize,
+    alignment: Alignment,
+) -> Vec<Span<'static>> {
+    let line_width: usize = line.iter().map(Span::width).sum();
+    let padding = column_width.saturating_sub(line_width);
+    let (left_padding, right_padding) = match alignment {
+        Alignment::None | Alignment::Left => (0, padding),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Right => (padding, 0),
+    };
+    line.insert(0, Span::raw(" ".repeat(left_padding)));
+    line.push(Span::raw(" ".repeat(right_padding)));
+    line
+}
@@ -1,42 +1,305 @@
-// use crate::markdown_renderer::parse_markdown_to_widgets;
-// use ratatui::{
-//     style::{Modifier, Style},
-//     text::{Span, Spans, Text},
-// };
-
-// #[test]
-// fn strong_is_bold() {
-//     compare_style("**", Style::default().add_modifier(Modifier::BOLD))
-// }
-
-// #[test]
-// fn emphasis_is_italics() {
-//     compare_style("*", Style::default().add_modifier(Modifier::ITALIC))
-// }
-
-// #[test]
-// fn nested_styles() {
-//     compare_style(
-//         "***",
-//         Style::default()
-//             .add_modifier(Modifier::ITALIC)
-//             .add_modifier(Modifier::BOLD),
-//     )
-// }
-
-// fn compare_style(enclosing_str: &str, style: Style) {
-//     let inner_str = "Test";
-//     let markdown_string = format!("{enclosing_str}{inner_str}{enclosing_str}");
-//     assert_eq!(
-//         Text::styled(inner_str, style),
-//         parse_markdown_to_widgets(markdown_string)
-//     )
-// }
-
-// // #[test]
-// // fn compare_ansi() {
-// //     let test_ansi = b"n";
-// //     let result_buffer = BufWriter::new(std::io::stdout());
-// //     // execute!(result_buffer, )
-// //     assert_eq!(result_buffer.buffer(), test_ansi)
-// // }
+use crate::{
+    config::theme::Theme,
+    markdown_renderer::{
+        document::{parse_document, Block, BlockKind, Inline},
+        html::render_html,
+        layout::layout_document,
+        table::render_table,
+    },
+    search::line_text,
+};
+use pulldown_cmark::{Alignment, HeadingLevel};
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+use unicode_width::UnicodeWidthStr;
+
+#[test]
+fn strong_is_bold() {
+    compare_style("**", Style::default().add_modifier(Modifier::BOLD))
+}
+
+#[test]
+fn emphasis_is_italics() {
+    compare_style("*", Style::default().add_modifier(Modifier::ITALIC))
+}
+
+#[test]
+fn nested_styles() {
+    compare_style(
+        "***",
+        Style::default()
+            .add_modifier(Modifier::ITALIC)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+#[test]
+fn headings_keep_their_level_and_source_lines() {
+    let document = parse_document("intro\n\n## Usage\n");
+    assert_eq!(
+        document.blocks[1],
+        Block {
+            kind: BlockKind::Heading(HeadingLevel::H2, vec![Inline::Text("Usage".into())]),
+            source_lines: 2..3,
+        }
+    );
+}
+
+#[test]
+fn lists_are_tight_unless_items_are_separated() {
+    let is_tight = |markdown_str| match &parse_document(markdown_str).blocks[0].kind {
+        BlockKind::List { tight, .. } => *tight,
+        kind => panic!("expected a list, got {kind:?}"),
+    };
+    assert!(is_tight("- one\n- two\n"));
+    assert!(!is_tight("- one\n\n- two\n"));
+}
+
+#[test]
+fn task_items_are_checked() {
+    let document = parse_document("- [ ] todo\n- [x] done\n");
+    let BlockKind::List { items, .. } = &document.blocks[0].kind else {
+        panic!("expected a list");
+    };
+    let tasks: Vec<_> = items.iter().map(|item| item.task).collect();
+    assert_eq!(tasks, [Some(false), Some(true)]);
+}
+
+#[test]
+fn alert_marker_is_stripped_from_blockquote() {
+    let document = parse_document("> [!WARNING]\n> Mind the gap\n");
+    let BlockKind::BlockQuote { alert, blocks } = &document.blocks[0].kind else {
+        panic!("expected a blockquote");
+    };
+    assert!(alert.is_some());
+    assert_eq!(
+        blocks[0].kind,
+        BlockKind::Paragraph(vec![Inline::Text("Mind the gap".into())])
+    );
+}
+
+#[test]
+fn rendered_lines_map_to_their_block_source_lines() {
+    let layout = layout_document(
+        &parse_document("# Title\n\nfirst\n\nsecond\n"),
+        80,
+        &Theme::default(),
+    );
+    let line_texts: Vec<String> = layout
+        .lines
+        .iter()
+        .map(|line| line.0.iter().map(|span| span.content.as_ref()).collect())
+        .collect();
+    assert_eq!(line_texts, ["# Title", "", "first", "", "second"]);
+    assert_eq!(layout.source_lines, [0, 0, 2, 4, 4]);
+}
+
+fn compare_style(enclosing_str: &str, style: Style) {
+    let inner_str = "Test";
+    let markdown_string = format!("{enclosing_str}{inner_str}{enclosing_str}");
+    let layout = layout_document(&parse_document(&markdown_string), 80, &Theme::default());
+    assert_eq!(layout.lines[0].0, vec![Span::styled(inner_str, style)]);
+}
+
+#[test]
+fn headings_are_collected_with_their_rendered_lines() {
+    let layout = layout_document(
+        &parse_document("# Title\n\ntext\n\n## *Usage*\n\n[^1]: ### Not listed\n"),
+        80,
+        &Theme::default(),
+    );
+    let headings: Vec<(HeadingLevel, &str, usize)> = layout
+        .headings
+        .iter()
+        .map(|heading| (heading.level, heading.title.as_str(), heading.line_index))
+        .collect();
+    assert_eq!(
+        headings,
+        [
+            (HeadingLevel::H1, "Title", 0),
+            (HeadingLevel::H2, "Usage", 4)
+        ]
+    );
+}
+
+#[test]
+fn links_are_positioned_on_the_lines_they_wrap_onto() {
+    let layout = layout_document(
+        &parse_document("see [the other doc](other.md#usage) now\n"),
+        12,
+        &Theme::default(),
+    );
+    assert_eq!(layout.links.len(), 1);
+    assert_eq!(layout.links[0].url, "other.md#usage");
+    assert_eq!(layout.links[0].segments, [(0, 4..8), (1, 0..9)]);
+}
+
+#[test]
+fn heading_anchors_are_unique() {
+    let layout = layout_document(
+        &parse_document("# Getting started!\n\n## Usage\n\n## Usage\n"),
+        80,
+        &Theme::default(),
+    );
+    let anchors: Vec<&str> = layout
+        .headings
+        .iter()
+        .map(|heading| heading.anchor.as_str())
+        .collect();
+    assert_eq!(anchors, ["getting-started", "usage", "usage-1"]);
+}
+
+#[test]
+fn html_headings_are_anchored_and_listed_in_table_of_contents() {
+    let html = render_html(
+        "# Fish & Chips\n\n## Fish & Chips\n",
+        "menu.md",
+        &Theme::default(),
+    );
+    assert!(html.contains("<title>menu.md</title>"));
+    assert!(
+        html.contains("<li class=\"toc-h1\"><a href=\"#fish--chips\">Fish &amp; Chips</a></li>")
+    );
+    assert!(
+        html.contains("<li class=\"toc-h2\"><a href=\"#fish--chips-1\">Fish &amp; Chips</a></li>")
+    );
+    assert!(html.contains("<h2 id=\"fish--chips-1\">"));
+}
+
+fn table_line_texts(rows: &[&[&str]], width: u16) -> Vec<String> {
+    let alignments = vec![Alignment::None; rows[0].len()];
+    let rows = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| vec![Span::raw(cell.to_string())])
+                .collect()
+        })
+        .collect();
+    render_table(&alignments, rows, width, Style::default())
+        .iter()
+        .map(line_text)
+        .collect()
+}
+
+#[test]
+fn table_cells_wrap_to_fit_the_width() {
+    let lines = table_line_texts(
+        &[
+            &["name", "about"],
+            &["grow", "live markdown viewer with GFM support"],
+        ],
+        30,
+    );
+    assert!(lines.iter().all(|line| line.width() <= 30), "{lines:#?}");
+    assert!(lines.iter().any(|line| line.contains("viewer")));
+    assert!(lines.len() > 5);
+}
+
+#[test]
+fn table_cells_too_narrow_to_wrap_are_truncated() {
+    let lines = table_line_texts(&[&["abcdef", "ghijkl", "mnopqr"]], 16);
+    assert_eq!(lines[1], "│ a… │ g… │ m… │");
+}
+
+#[test]
+fn tables_narrower_than_their_borders_drop_them() {
+    let lines = table_line_texts(&[&["one", "two", "three"], &["a", "b", "c"]], 5);
+    assert_eq!(lines, ["one", "two", "three", "─────", "a", "b", "c"]);
+}
+
+#[test]
+fn autolinks_are_given_a_scheme() {
+    let layout = layout_document(
+        &parse_document("visit www.example.com or mail <foo@bar.com>\n"),
+        80,
+        &Theme::default(),
+    );
+    let urls: Vec<&str> = layout.links.iter().map(|link| link.url.as_str()).collect();
+    assert_eq!(urls, ["http://www.example.com", "mailto:foo@bar.com"]);
+    assert_eq!(
+        line_text(&layout.lines[0]),
+        "visit www.example.com or mail foo@bar.com"
+    );
+}
+
+#[test]
+fn scroll_anchor_keeps_the_same_block_in_view() {
+    let layout =
+        |markdown_str| layout_document(&parse_document(markdown_str), 80, &Theme::default());
+    let old_layout = layout("# Title\n\nfirst\n\n## Usage\n\nkept in view\n\nbelow\n");
+    let old_offset = old_layout
+        .lines
+        .iter()
+        .position(|line| line_text(line) == "kept in view")
+        .unwrap();
+    let scroll_anchor = old_layout.scroll_anchor(old_offset).unwrap();
+
+    let new_layout =
+        layout("# Title\n\ninserted\n\nabove\n\nfirst\n\n## Usage\n\nkept in view\n\nbelow\n");
+    let new_offset = new_layout.anchored_scroll_offset(&scroll_anchor);
+    assert_eq!(line_text(&new_layout.lines[new_offset]), "kept in view");
+
+    let shortened_layout = layout("# Title\n");
+    assert!(shortened_layout.anchored_scroll_offset(&scroll_anchor) < shortened_layout.lines.len());
+}
+
+#[test]
+fn html_links_that_run_script_lose_their_destination() {
+    let html = render_html(
+        "[a](javascript:alert(1)) [b](JavaScript:alert(1)) \
+         [c](data:text/html,x) [d](https://example.com)\n",
+        "links.md",
+        &Theme::default(),
+    );
+    assert!(!html.to_ascii_lowercase().contains("href=\"javascript"));
+    assert!(!html.contains("href=\"data"));
+    assert!(html.contains("<a>a</a>"));
+    assert!(html.contains("<a href=\"https://example.com\">d</a>"));
+}
+
+#[test]
+fn code_block_tabs_expand_to_the_next_tab_stop() {
+    let layout = layout_document(
+        &parse_document("```\na\tx\nabc\tx\n日\tx\n```\n"),
+        80,
+        &Theme::default(),
+    );
+    let x_columns: Vec<usize> = layout
+        .lines
+        .iter()
+        .map(line_text)
+        .filter_map(|line| line.find('x').map(|x_index| line[..x_index].width()))
+        .collect();
+    assert_eq!(x_columns.len(), 3);
+    assert!(
+        x_columns.iter().all(|column| *column == x_columns[0]),
+        "{x_columns:?}"
+    );
+}
+
+#[test]
+fn inline_html_stays_within_tight_list_items() {
+    let layout = layout_document(
+        &parse_document("- Press <kbd>q</kbd> to quit\n- next\n"),
+        80,
+        &Theme::default(),
+    );
+    let lines: Vec<String> = layout.lines.iter().map(line_text).collect();
+    assert_eq!(lines[..2], ["• Press q to quit", "• next"]);
+}
+
+#[test]
+fn html_images_keep_their_source() {
+    let markdown_str = "![diagram](img.png) ![x](javascript:alert(1))\n";
+    let html = render_html(markdown_str, "images.md", &Theme::default());
+    assert!(
+        html.contains("<img src=\"img.png\" alt=\"diagram\">"),
+        "{html}"
+    );
+    assert!(html.contains("<img alt=\"x\">"), "{html}");
+    let layout = layout_document(&parse_document(markdown_str), 80, &Theme::default());
+    assert_eq!(line_text(&layout.lines[0]), "diagram x");
+}
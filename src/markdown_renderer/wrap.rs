@@ -0,0 +1,97 @@
+use ratatui::{style::Style, text::Span};
+use unicode_width::UnicodeWidthChar;
+
+/// Word wraps a line of spans into lines no wider than `max_width` columns.
+/// Whitespace is dropped at the start of wrapped lines, and words that can't
+/// fit on a line of their own are split. Always returns at least one line.
+pub fn wrap_spans(line: Vec<Span<'static>>, max_width: usize) -> Vec<Vec<Span<'static>>> {
+    let max_width = max_width.max(1);
+    let mut wrapped_lines = WrappedLines::new(max_width);
+    for (is_whitespace, word) in split_words(line) {
+        let word_width: usize = word.iter().map(Span::width).sum();
+        if wrapped_lines.line_width + word_width > max_width && wrapped_lines.line_width != 0 {
+            wrapped_lines.break_line();
+        }
+        if is_whitespace && wrapped_lines.line_width == 0 && wrapped_lines.lines.len() > 1 {
+            continue;
+        }
+        for span in word {
+            wrapped_lines.push_chars(&span.content, span.style);
+        }
+    }
+    wrapped_lines.lines
+}
+
+/// Splits a line of spans into chunks no wider than `max_width` columns,
+/// without regard to word boundaries. Always returns at least one chunk.
+pub fn split_at_width(line: Vec<Span<'static>>, max_width: usize) -> Vec<Vec<Span<'static>>> {
+    let mut wrapped_lines = WrappedLines::new(max_width.max(1));
+    for span in line {
+        wrapped_lines.push_chars(&span.content, span.style);
+    }
+    wrapped_lines.lines
+}
+
+/// Splits a line into alternating runs of whitespace and words, flagged by the former.
+/// Words may span several differently styled spans, e.g. `**bold**,`.
+fn split_words(line: Vec<Span<'static>>) -> Vec<(bool, Vec<Span<'static>>)> {
+    let mut words: Vec<(bool, Vec<Span<'static>>)> = Vec::new();
+    for span in line {
+        for ch in span.content.chars() {
+            let is_whitespace = ch.is_whitespace();
+            match words.last_mut() {
+                Some((word_is_whitespace, word)) if *word_is_whitespace == is_whitespace => {
+                    match word.last_mut() {
+                        Some(last_span) if last_span.style == span.style => {
+                            last_span.content.to_mut().push(ch)
+                        }
+                        _ => word.push(Span::styled(ch.to_string(), span.style)),
+                    }
+                }
+                _ => words.push((
+                    is_whitespace,
+                    vec![Span::styled(ch.to_string(), span.style)],
+                )),
+            }
+        }
+    }
+    words
+}
+
+struct WrappedLines {
+    lines: Vec<Vec<Span<'static>>>,
+    line_width: usize,
+    max_width: usize,
+}
+
+impl WrappedLines {
+    fn new(max_width: usize) -> Self {
+        WrappedLines {
+            lines: vec![Vec::new()],
+            line_width: 0,
+            max_width,
+        }
+    }
+
+    fn break_line(&mut self) {
+        self.lines.push(Vec::new());
+        self.line_width = 0;
+    }
+
+    /// Pushes `text` char by char, breaking lines once full. Adjacent text
+    /// of the same style is merged into a single span.
+    fn push_chars(&mut self, text: &str, style: Style) {
+        for ch in text.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            if self.line_width + ch_width > self.max_width && self.line_width != 0 {
+                self.break_line();
+            }
+            let line = self.lines.last_mut().expect("At least one line.");
+            match line.last_mut() {
+                Some(last_span) if last_span.style == style => last_span.content.to_mut().push(ch),
+                _ => line.push(Span::styled(ch.to_string(), style)),
+            }
+            self.line_width += ch_width;
+        }
+    }
+}
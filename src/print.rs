@@ -0,0 +1,137 @@
+use anyhow::Result;
+use crossterm::{
+    queue,
+    style::{
+        Attribute, Color as CrosstermColor, Print, ResetColor, SetAttribute, SetBackgroundColor,
+        SetForegroundColor,
+    },
+    terminal,
+};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Spans,
+};
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use crate::{config::theme::Theme, markdown_renderer::render_markdown, search::line_text};
+
+// Width to render at when neither given, configured nor printing to a terminal.
+const DEFAULT_PRINT_WIDTH: u16 = 80;
+
+/// Writes the rendered files to stdout one after another, preceded by any markdown
+/// read from stdin. Styled with ANSI escape codes when printing to a terminal,
+/// and as plain text otherwise, e.g. when piped.
+pub fn print_files(
+    file_paths: &[PathBuf],
+    stdin_contents: Option<&str>,
+    width: Option<u16>,
+    wrap_width: Option<u16>,
+    theme: &Theme,
+) -> Result<()> {
+    let styled = io::stdout().is_terminal();
+    let terminal_width = styled
+        .then(|| terminal::size().ok().map(|(columns, _)| columns))
+        .flatten();
+    let width = width.unwrap_or(match (terminal_width, wrap_width) {
+        (Some(terminal_width), Some(wrap_width)) => terminal_width.min(wrap_width),
+        (terminal_width, wrap_width) => {
+            terminal_width.or(wrap_width).unwrap_or(DEFAULT_PRINT_WIDTH)
+        }
+    });
+    let markdown_strings = stdin_contents
+        .map(|stdin_contents| Ok(stdin_contents.to_string()))
+        .into_iter()
+        .chain(file_paths.iter().map(fs::read_to_string));
+    let mut stdout = io::stdout().lock();
+    for (document_index, markdown_string) in markdown_strings.enumerate() {
+        let layout = render_markdown(&markdown_string?, width, theme);
+        // Documents are separated by a blank line.
+        let separator = if document_index > 0 { "\n" } else { "" };
+        let print_result = write!(stdout, "{separator}")
+            .and_then(|_| print_lines(&mut stdout, &layout.lines, styled));
+        match print_result {
+            // Readers such as `head` may stop reading before everything is printed.
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            print_result => print_result?,
+        }
+    }
+    Ok(())
+}
+
+fn print_lines(stdout: &mut impl Write, lines: &[Spans], styled: bool) -> io::Result<()> {
+    for line in lines {
+        if !styled {
+            writeln!(stdout, "{}", line_text(line))?;
+            continue;
+        }
+        for span in &line.0 {
+            if span.style == Style::default() {
+                queue!(stdout, Print(&span.content))?;
+            } else {
+                queue_style(stdout, span.style)?;
+                queue!(
+                    stdout,
+                    Print(&span.content),
+                    SetAttribute(Attribute::Reset),
+                    ResetColor
+                )?;
+            }
+        }
+        writeln!(stdout)?;
+    }
+    stdout.flush()
+}
+
+fn queue_style(stdout: &mut impl Write, style: Style) -> io::Result<()> {
+    if let Some(foreground) = style.fg {
+        queue!(stdout, SetForegroundColor(crossterm_color(foreground)))?;
+    }
+    if let Some(background) = style.bg {
+        queue!(stdout, SetBackgroundColor(crossterm_color(background)))?;
+    }
+    let attributes = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::SLOW_BLINK, Attribute::SlowBlink),
+        (Modifier::RAPID_BLINK, Attribute::RapidBlink),
+        (Modifier::REVERSED, Attribute::Reverse),
+        (Modifier::HIDDEN, Attribute::Hidden),
+        (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+    ];
+    for (modifier, attribute) in attributes {
+        if style.add_modifier.contains(modifier) {
+            queue!(stdout, SetAttribute(attribute))?;
+        }
+    }
+    Ok(())
+}
+
+fn crossterm_color(color: Color) -> CrosstermColor {
+    match color {
+        Color::Reset => CrosstermColor::Reset,
+        Color::Black => CrosstermColor::Black,
+        Color::Red => CrosstermColor::DarkRed,
+        Color::Green => CrosstermColor::DarkGreen,
+        Color::Yellow => CrosstermColor::DarkYellow,
+        Color::Blue => CrosstermColor::DarkBlue,
+        Color::Magenta => CrosstermColor::DarkMagenta,
+        Color::Cyan => CrosstermColor::DarkCyan,
+        Color::Gray => CrosstermColor::Grey,
+        Color::DarkGray => CrosstermColor::DarkGrey,
+        Color::LightRed => CrosstermColor::Red,
+        Color::LightGreen => CrosstermColor::Green,
+        Color::LightBlue => CrosstermColor::Blue,
+        Color::LightYellow => CrosstermColor::Yellow,
+        Color::LightMagenta => CrosstermColor::Magenta,
+        Color::LightCyan => CrosstermColor::Cyan,
+        Color::White => CrosstermColor::White,
+        Color::Rgb(r, g, b) => CrosstermColor::Rgb { r, g, b },
+        Color::Indexed(index) => CrosstermColor::AnsiValue(index),
+    }
+}
@@ -0,0 +1,293 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::theme::Theme;
+use ratatui::{
+    style::Style,
+    text::{Span, Spans},
+};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+pub enum SearchCommand {
+    Open(SearchDirection),
+    Input(char),
+    DeleteChar,
+    ToggleIgnoreCase,
+    ToggleRegex,
+    Confirm,
+    Cancel,
+    NextMatch,
+    PrevMatch,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+impl SearchDirection {
+    fn reversed(self) -> Self {
+        match self {
+            SearchDirection::Forward => SearchDirection::Backward,
+            SearchDirection::Backward => SearchDirection::Forward,
+        }
+    }
+}
+
+/// Match of the search query within the rendered text of a line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchMatch {
+    pub line_index: usize,
+    pub byte_range: Range<usize>,
+}
+
+impl SearchMatch {
+    fn position(&self) -> (usize, usize) {
+        (self.line_index, self.byte_range.start)
+    }
+}
+
+pub struct Search {
+    pub query: String,
+    pub direction: SearchDirection,
+    pub ignore_case: bool,
+    pub regex: bool,
+    // Whether the query is still being typed into the prompt.
+    pub prompting: bool,
+    // Scroll offset when the search was opened, restored if cancelled.
+    pub origin_scroll_offset: usize,
+    // Matches in the rendered lines of the focused view.
+    pub matches: Vec<SearchMatch>,
+    pub current_match: Option<SearchMatch>,
+    invalid_pattern: bool,
+    // Query, options and layout revision the matches were found for.
+    searched: Option<(String, bool, bool, u64)>,
+}
+
+impl Search {
+    pub fn new(direction: SearchDirection, origin_scroll_offset: usize) -> Self {
+        Search {
+            query: String::new(),
+            direction,
+            ignore_case: false,
+            regex: false,
+            prompting: true,
+            origin_scroll_offset,
+            matches: Vec::new(),
+            current_match: None,
+            invalid_pattern: false,
+            searched: None,
+        }
+    }
+
+    /// Finds all matches in the text of the given lines, ignoring their styling.
+    /// Regex queries that don't compile yield no matches. The matches are kept
+    /// until the query, its options or the revision of the layout change.
+    pub fn refresh_matches(&mut self, lines: &[Spans], layout_revision: u64) {
+        let searched = (
+            self.query.clone(),
+            self.ignore_case,
+            self.regex,
+            layout_revision,
+        );
+        if self.searched.as_ref() == Some(&searched) {
+            return;
+        }
+        self.searched = Some(searched);
+        self.matches.clear();
+        let pattern = match self.pattern() {
+            Some(Ok(pattern)) => pattern,
+            Some(Err(_)) | None => {
+                self.invalid_pattern = !self.query.is_empty();
+                return;
+            }
+        };
+        self.invalid_pattern = false;
+        for (line_index, line) in lines.iter().enumerate() {
+            let line_text = line_text(line);
+            self.matches.extend(
+                pattern
+                    .find_iter(&line_text)
+                    .filter(|found| !found.range().is_empty())
+                    .map(|found| SearchMatch {
+                        line_index,
+                        byte_range: found.range(),
+                    }),
+            );
+        }
+    }
+
+    fn pattern(&self) -> Option<Result<Regex, regex::Error>> {
+        if self.query.is_empty() {
+            return None;
+        }
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        Some(
+            RegexBuilder::new(&pattern)
+                .case_insensitive(self.ignore_case)
+                .build(),
+        )
+    }
+
+    /// Moves to the first match from the line the search was opened at, as the query is typed.
+    pub fn select_first_match(&mut self) {
+        self.current_match = next_match(
+            &self.matches,
+            (self.origin_scroll_offset, 0),
+            self.direction,
+            true,
+        )
+        .cloned();
+    }
+
+    /// Moves to the next match in the search direction, or the opposite one if `reversed`.
+    pub fn select_next_match(&mut self, reversed: bool) {
+        let Some(current_match) = &self.current_match else {
+            return self.select_first_match();
+        };
+        let direction = if reversed {
+            self.direction.reversed()
+        } else {
+            self.direction
+        };
+        self.current_match =
+            next_match(&self.matches, current_match.position(), direction, false).cloned();
+    }
+
+    /// Search prompt while typing, otherwise the match count.
+    pub fn status(&self, theme: &Theme) -> Spans<'static> {
+        if self.prompting {
+            let prompt_char = match self.direction {
+                SearchDirection::Forward => '/',
+                SearchDirection::Backward => '?',
+            };
+            let mut status = vec![Span::raw(format!("{prompt_char}{}", self.query))];
+            if self.regex {
+                status.push(Span::styled(" [regex]", theme.search_option));
+            }
+            if self.ignore_case {
+                status.push(Span::styled(" [ignore case]", theme.search_option));
+            }
+            if self.invalid_pattern {
+                status.push(Span::styled(" invalid regex", theme.status_error));
+            }
+            return Spans::from(status);
+        }
+        let current_match_index = self.current_match.as_ref().and_then(|current_match| {
+            self.matches
+                .iter()
+                .position(|search_match| search_match == current_match)
+        });
+        match current_match_index {
+            Some(match_index) => {
+                Spans::from(format!("match {}/{}", match_index + 1, self.matches.len()))
+            }
+            None => Spans::from(Span::styled(
+                format!("no matches for \"{}\"", self.query),
+                theme.status_error,
+            )),
+        }
+    }
+
+    /// Highlights the matches of a line, the current match standing out from the rest.
+    pub fn highlight_line(
+        &self,
+        line: Spans<'static>,
+        line_index: usize,
+        theme: &Theme,
+    ) -> Spans<'static> {
+        let highlights: Vec<(Range<usize>, Style)> = self
+            .matches
+            .iter()
+            .filter(|search_match| search_match.line_index == line_index)
+            .map(|search_match| {
+                let is_current = self.current_match.as_ref() == Some(search_match);
+                let match_style = if is_current {
+                    theme.current_search_match
+                } else {
+                    theme.search_match
+                };
+                (search_match.byte_range.clone(), match_style)
+            })
+            .collect();
+        if highlights.is_empty() {
+            return line;
+        }
+        highlight_ranges(line, &highlights)
+    }
+}
+
+/// Next match after, or before if searching backwards, the given line and byte position.
+/// Wraps around at the ends of the document. Searching forwards
+/// may include a match starting at the position itself.
+pub fn next_match(
+    matches: &[SearchMatch],
+    position: (usize, usize),
+    direction: SearchDirection,
+    include_position: bool,
+) -> Option<&SearchMatch> {
+    match direction {
+        SearchDirection::Forward => matches
+            .iter()
+            .find(|search_match| {
+                search_match.position() > position
+                    || (include_position && search_match.position() == position)
+            })
+            .or_else(|| matches.first()),
+        SearchDirection::Backward => matches
+            .iter()
+            .rev()
+            .find(|search_match| search_match.position() < position)
+            .or_else(|| matches.last()),
+    }
+}
+
+pub fn line_text(line: &Spans) -> String {
+    line.0.iter().map(|span| span.content.as_ref()).collect()
+}
+
+/// Splits spans at the bounds of the byte ranges, patching the style of the parts within them.
+/// Ranges are expected to be sorted and not to overlap.
+pub fn highlight_ranges(
+    line: Spans<'static>,
+    highlights: &[(Range<usize>, Style)],
+) -> Spans<'static> {
+    let mut highlighted_spans = Vec::new();
+    let mut span_start = 0;
+    for span in line.0 {
+        let span_end = span_start + span.content.len();
+        let mut cut_start = span_start;
+        for (byte_range, highlight_style) in highlights {
+            let highlight_start = byte_range.start.clamp(span_start, span_end);
+            let highlight_end = byte_range.end.clamp(span_start, span_end);
+            if highlight_start >= highlight_end {
+                continue;
+            }
+            if cut_start < highlight_start {
+                highlighted_spans.push(Span::styled(
+                    span.content[cut_start - span_start..highlight_start - span_start].to_string(),
+                    span.style,
+                ));
+            }
+            highlighted_spans.push(Span::styled(
+                span.content[highlight_start - span_start..highlight_end - span_start].to_string(),
+                span.style.patch(*highlight_style),
+            ));
+            cut_start = highlight_end;
+        }
+        if cut_start < span_end {
+            highlighted_spans.push(Span::styled(
+                span.content[cut_start - span_start..].to_string(),
+                span.style,
+            ));
+        }
+        span_start = span_end;
+    }
+    Spans::from(highlighted_spans)
+}
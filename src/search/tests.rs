@@ -0,0 +1,91 @@
+use crate::{
+    config::theme::Theme,
+    search::{next_match, Search, SearchDirection, SearchMatch},
+};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+};
+
+#[test]
+fn matches_rendered_text_across_spans() {
+    let lines = vec![Spans::from(vec![
+        Span::styled("# ", Style::default().fg(Color::Magenta)),
+        Span::styled("Hea", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw("ding"),
+    ])];
+    let search = search_for("heading", &lines, |search| search.ignore_case = true);
+    assert_eq!(
+        search.matches,
+        [SearchMatch {
+            line_index: 0,
+            byte_range: 2..9
+        }]
+    );
+}
+
+#[test]
+fn literal_queries_are_case_sensitive_by_default() {
+    let lines = vec![Spans::from("Grow grow")];
+    let search = search_for("grow", &lines, |_| ());
+    assert_eq!(search.matches.len(), 1);
+}
+
+#[test]
+fn regex_queries_match_patterns() {
+    let lines = vec![Spans::from("v1.2 and v10.3")];
+    let search = search_for(r"v\d+\.\d", &lines, |search| search.regex = true);
+    assert_eq!(search.matches.len(), 2);
+    let search = search_for(r"v\d+\.\d", &lines, |_| ());
+    assert!(search.matches.is_empty());
+}
+
+#[test]
+fn matches_are_kept_until_the_query_or_layout_change() {
+    let mut search = search_for("grow", &[Spans::from("grow")], |_| ());
+    search.refresh_matches(&[Spans::from("shrink")], 1);
+    assert_eq!(search.matches.len(), 1);
+    search.refresh_matches(&[Spans::from("shrink")], 2);
+    assert!(search.matches.is_empty());
+    search.query = "shrink".to_string();
+    search.refresh_matches(&[Spans::from("shrink")], 2);
+    assert_eq!(search.matches.len(), 1);
+}
+
+#[test]
+fn next_match_wraps_around() {
+    let matches = [(0, 3), (2, 0), (5, 1)].map(|(line_index, start)| SearchMatch {
+        line_index,
+        byte_range: start..start + 1,
+    });
+    let next = |position, direction| next_match(&matches, position, direction, false);
+    assert_eq!(next((2, 0), SearchDirection::Forward), Some(&matches[2]));
+    assert_eq!(next((5, 1), SearchDirection::Forward), Some(&matches[0]));
+    assert_eq!(next((0, 3), SearchDirection::Backward), Some(&matches[2]));
+}
+
+#[test]
+fn highlights_split_spans_at_match_bounds() {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let lines = vec![Spans::from(vec![
+        Span::raw("one "),
+        Span::styled("two", bold),
+    ])];
+    let mut search = search_for("e t", &lines, |_| ());
+    search.select_first_match();
+    let contents: Vec<String> = search
+        .highlight_line(lines[0].clone(), 0, &Theme::default())
+        .0
+        .into_iter()
+        .map(|span| span.content.into_owned())
+        .collect();
+    assert_eq!(contents, ["on", "e ", "t", "wo"]);
+}
+
+fn search_for(query: &str, lines: &[Spans], configure: impl FnOnce(&mut Search)) -> Search {
+    let mut search = Search::new(SearchDirection::Forward, 0);
+    search.query = query.to_string();
+    configure(&mut search);
+    search.refresh_matches(lines, 1);
+    search
+}
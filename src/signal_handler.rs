@@ -0,0 +1,24 @@
+use crate::{
+    thread_helpers::{send_command, send_error_command},
+    Command,
+};
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM, SIGTSTP},
+    iterator::Signals,
+};
+use std::sync::mpsc::Sender;
+
+/// Closes the application on termination signals, restoring the terminal as on quitting,
+/// and suspends it on SIGTSTP, such as sent by `kill -TSTP` rather than typing Ctrl-Z.
+pub fn signal_handler(cmd_sender: Sender<anyhow::Result<Command>>) {
+    let mut signals = match Signals::new([SIGTERM, SIGHUP, SIGINT, SIGTSTP]) {
+        Ok(signals) => signals,
+        Err(error) => return send_error_command(&cmd_sender, error),
+    };
+    for signal in signals.forever() {
+        match signal {
+            SIGTSTP => send_command(&cmd_sender, Command::Suspend),
+            _ => send_command(&cmd_sender, Command::Close),
+        }
+    }
+}
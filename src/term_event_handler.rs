@@ -1,39 +1,84 @@
 use crate::{
     application::UpdateView,
+    config::keymap::Keymap,
+    search::SearchCommand,
     thread_helpers::{send_command, send_error_command},
     Command,
 };
-use crossterm::event::{read, Event as CrosstermEvent, KeyCode::*, KeyModifiers};
+use crossterm::event::{
+    read, Event as CrosstermEvent, KeyCode::*, KeyEvent, KeyModifiers, MouseButton, MouseEventKind,
+};
 use std::sync::mpsc::Sender;
 
-pub fn event_loop(cmd_sender: Sender<anyhow::Result<Command>>) {
-    loop {
-        match read() {
-            Ok(crossterm_event) => match crossterm_event {
-                CrosstermEvent::Resize(_, _) => {
-                    send_command(&cmd_sender, Command::Update(UpdateView::Resize));
-                }
-                CrosstermEvent::Key(keyevent) => match (keyevent.modifiers, keyevent.code) {
-                    (KeyModifiers::NONE, Char(ch)) if ch == 'd' => {
-                        send_command(&cmd_sender, Command::Scroll(1));
-                    }
-                    (KeyModifiers::NONE, Char(ch)) if ch == 'u' => {
-                        send_command(&cmd_sender, Command::Scroll(-1));
-                    }
-                    (KeyModifiers::NONE, Char(ch)) if ch == '<' => {
-                        send_command(&cmd_sender, Command::PrevView);
+pub fn event_loop(
+    keymap: Keymap,
+    mouse_scroll_lines: i16,
+) -> impl FnOnce(Sender<anyhow::Result<Command>>) {
+    move |cmd_sender: Sender<anyhow::Result<Command>>| {
+        // Keys are typed into the search prompt while it's open.
+        let mut search_prompt_open = false;
+        loop {
+            match read() {
+                Ok(crossterm_event) => match crossterm_event {
+                    CrosstermEvent::Resize(_, _) => {
+                        send_command(&cmd_sender, Command::Update(UpdateView::Resize));
                     }
-                    (KeyModifiers::NONE, Char(ch)) if ch == '>' => {
-                        send_command(&cmd_sender, Command::NextView);
+                    CrosstermEvent::Mouse(mouse_event) => match mouse_event.kind {
+                        MouseEventKind::ScrollDown => {
+                            send_command(&cmd_sender, Command::Scroll(mouse_scroll_lines));
+                        }
+                        MouseEventKind::ScrollUp => {
+                            send_command(&cmd_sender, Command::Scroll(-mouse_scroll_lines));
+                        }
+                        // Clicks are ignored while typing a search, as they would move the view.
+                        MouseEventKind::Down(MouseButton::Left) if !search_prompt_open => {
+                            send_command(
+                                &cmd_sender,
+                                Command::Click(mouse_event.column, mouse_event.row),
+                            );
+                        }
+                        MouseEventKind::Down(MouseButton::Middle) if !search_prompt_open => {
+                            send_command(
+                                &cmd_sender,
+                                Command::MiddleClick(mouse_event.column, mouse_event.row),
+                            );
+                        }
+                        _ => (),
+                    },
+                    CrosstermEvent::Key(keyevent) if search_prompt_open => {
+                        if let Some(search_command) = search_prompt_command(keyevent) {
+                            search_prompt_open = !matches!(
+                                search_command,
+                                SearchCommand::Confirm | SearchCommand::Cancel
+                            );
+                            send_command(&cmd_sender, Command::Search(search_command));
+                        }
                     }
-                    (KeyModifiers::CONTROL, Char(ch)) if ch == 'c' => {
-                        send_command(&cmd_sender, Command::Close);
+                    CrosstermEvent::Key(keyevent) => {
+                        if let Some(command) = keymap.command(keyevent) {
+                            search_prompt_open =
+                                matches!(command, Command::Search(SearchCommand::Open(_)));
+                            send_command(&cmd_sender, command);
+                        }
                     }
                     _ => (),
                 },
-                _ => (),
-            },
-            Err(error) => send_error_command(&cmd_sender, error),
+                Err(error) => send_error_command(&cmd_sender, error),
+            }
         }
     }
 }
+
+/// Alt-c and Alt-r toggle case-insensitive and regex matching, respectively.
+fn search_prompt_command(keyevent: KeyEvent) -> Option<SearchCommand> {
+    let search_command = match (keyevent.modifiers, keyevent.code) {
+        (_, Enter) => SearchCommand::Confirm,
+        (_, Esc) | (KeyModifiers::CONTROL, Char('c')) => SearchCommand::Cancel,
+        (_, Backspace) => SearchCommand::DeleteChar,
+        (KeyModifiers::ALT, Char('c')) => SearchCommand::ToggleIgnoreCase,
+        (KeyModifiers::ALT, Char('r')) => SearchCommand::ToggleRegex,
+        (KeyModifiers::NONE | KeyModifiers::SHIFT, Char(ch)) => SearchCommand::Input(ch),
+        _ => return None,
+    };
+    Some(search_command)
+}
@@ -0,0 +1,75 @@
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use signal_hook::{consts::SIGTSTP, low_level::emulate_default_handler};
+use std::{
+    io, panic, process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+// Whether the terminal is currently taken over, so that it's only restored once,
+// whichever of closing, dropping the guard or panicking gets to it first.
+static TERMINAL_TAKEN_OVER: AtomicBool = AtomicBool::new(false);
+
+/// Restores the terminal when dropped, such as when returning early on errors.
+pub struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // Nothing left to report errors to at this point.
+        restore_terminal().ok();
+    }
+}
+
+/// Switches to raw mode on the alternate screen, capturing the mouse and hiding the cursor.
+pub fn take_over_terminal() -> io::Result<TerminalGuard> {
+    install_panic_hook();
+    // Guarding before switching, as switching may fail halfway through.
+    let terminal_guard = TerminalGuard;
+    enter_terminal()?;
+    Ok(terminal_guard)
+}
+
+/// Hands the terminal back to the shell and stops the process, as Ctrl-Z would
+/// outside of raw mode. Takes the terminal over again once continued, e.g. by `fg`.
+pub fn suspend_process() -> io::Result<()> {
+    restore_terminal()?;
+    // Stopping the process even though SIGTSTP is otherwise handled by the application.
+    emulate_default_handler(SIGTSTP)?;
+    enter_terminal()
+}
+
+fn enter_terminal() -> io::Result<()> {
+    TERMINAL_TAKEN_OVER.store(true, Ordering::SeqCst);
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Hide)
+}
+
+pub fn restore_terminal() -> io::Result<()> {
+    if !TERMINAL_TAKEN_OVER.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        DisableMouseCapture,
+        LeaveAlternateScreen,
+        Show
+    )
+}
+
+/// Restores the terminal before the panic message is printed, as it would otherwise
+/// be lost to the alternate screen. Exits right after, as the application can't
+/// carry on without any of its threads, and panics elsewhere than the main
+/// thread would leave it hanging.
+fn install_panic_hook() {
+    let default_panic_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        restore_terminal().ok();
+        default_panic_hook(panic_info);
+        process::exit(101);
+    }));
+}